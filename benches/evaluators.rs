@@ -0,0 +1,66 @@
+//! Compares the evaluator backends on the same pseudo-random hands.
+//! Run with `cargo bench --features lookup-table`
+
+use poker_bot_v2::{Card, highest_hand};
+use std::{hint::black_box, time::Instant};
+
+const NUM_HANDS: usize = 1_000_000;
+
+/// Deterministic pseudo-random seven card hands (xorshift), without duplicate cards
+fn hands() -> Vec<[Card; 7]> {
+    let deck: Vec<Card> = (0..52).filter_map(Card::from_index).collect();
+
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    (0..NUM_HANDS)
+        .map(|_| {
+            let mut used = 0_u64;
+            std::array::from_fn(|_| {
+                loop {
+                    let index = (next() % 52) as usize;
+                    if used & 1 << index == 0 {
+                        used |= 1 << index;
+                        break deck[index];
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+fn bench(name: &str, hands: &[[Card; 7]], evaluator: impl Fn([Card; 7]) -> poker_bot_v2::HandRank) {
+    let start = Instant::now();
+    for &hand in hands {
+        black_box(evaluator(black_box(hand)));
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{name}: {:.1} ns/hand ({} hands in {elapsed:?})",
+        elapsed.as_nanos() as f64 / hands.len() as f64,
+        hands.len()
+    );
+}
+
+fn main() {
+    let hands = hands();
+
+    bench("bitmaps", &hands, highest_hand::hand_rank);
+
+    #[cfg(feature = "lookup-table")]
+    {
+        // Build the tables outside of the measurement
+        poker_bot_v2::lookup_table::hand_rank(hands[0]);
+        bench(
+            "lookup table",
+            &hands,
+            poker_bot_v2::lookup_table::hand_rank,
+        );
+    }
+}
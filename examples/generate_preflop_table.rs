@@ -0,0 +1,33 @@
+//! Generates the preflop equity table and writes it to a file.
+//! Run with `cargo run --release --example generate_preflop_table -- <path> [samples] [seed]`
+
+use poker_bot_v2::{monte_carlo::MIN_SAMPLES, preflop::PreflopTable};
+use std::{env, num::NonZeroUsize, process::ExitCode, thread, time::Instant};
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let Some(path) = args.next() else {
+        eprintln!("Usage: generate_preflop_table <path> [samples per multiway equity] [seed]");
+        return ExitCode::FAILURE;
+    };
+    let (Ok(samples @ MIN_SAMPLES..), Ok(seed)) = (
+        args.next().map_or(Ok(100_000), |samples| samples.parse()),
+        args.next().map_or(Ok(0), |seed| seed.parse()),
+    ) else {
+        eprintln!(
+            "The number of samples has to be at least {MIN_SAMPLES}, and the seed a positive integer"
+        );
+        return ExitCode::FAILURE;
+    };
+
+    let threads = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let start = Instant::now();
+    let table = PreflopTable::generate(samples, seed, threads);
+    println!("Generated in {:?}", start.elapsed());
+
+    if let Err(error) = table.save(&path) {
+        eprintln!("Failed to write {path}: {error}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
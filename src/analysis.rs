@@ -0,0 +1,289 @@
+use crate::{
+    Card, Error, Hand, HandRank,
+    card_set::CardSet,
+    check_board,
+    highest_hand::{ColorValueBitmaps, MultiColored, ValueBitmap},
+};
+
+/// How many values complete a straight, with at least one of the hand cards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StraightDraw {
+    None,
+    /// One value, e.g. 9-7 on 8-5-2 needs a six
+    Gutshot,
+    /// Two values, either open ended (9-8 on 7-6-2) or a double gutshot (9-7 on J-8-5)
+    OpenEnded,
+}
+
+/// Properties of the board alone
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BoardTexture {
+    /// At least two cards share a value
+    pub paired: bool,
+    /// All cards have the same color
+    pub monotone: bool,
+    /// The cards have exactly two colors, and at least two cards share one of them
+    pub two_tone: bool,
+    /// No two cards share a color
+    pub rainbow: bool,
+    /// At least two cards have neighbouring values (with the ace also below the two)
+    pub connected: bool,
+    /// At least three values fit into a straight, so two hand cards can complete it
+    pub straight_possible: bool,
+    /// At least three cards of one color, so two hand cards can complete a flush
+    pub flush_possible: bool,
+}
+
+/// What a hand currently is and could become with the remaining board cards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// The made hand, including the board
+    pub hand: Hand,
+    pub rank: HandRank,
+    /// The made hand of the board alone, to see whether the hand cards improve it
+    pub board_hand: Hand,
+    /// Four cards of a color, with at least one hand card. Only with cards to come
+    pub flush_draw: bool,
+    /// Three cards of a color on the flop, with at least one hand card
+    pub backdoor_flush_draw: bool,
+    /// Only with cards to come and without a straight yet
+    pub straight_draw: StraightDraw,
+    /// Two more values complete a straight on the flop, but no single one does
+    pub backdoor_straight_draw: bool,
+    /// The number of hand cards higher than every board card
+    pub overcards: u8,
+    /// The cards that improve the made hand on the next street, more than they improve the board alone.
+    /// Only on the flop and turn
+    pub outs: Vec<Card>,
+    pub texture: BoardTexture,
+}
+
+/// Analyzes the hand on the board, which can have 0 to 5 cards.
+/// Returns an error on duplicate cards, or if the board has too many cards
+pub fn analyze(hand: [Card; 2], board: &[Card]) -> Result<Analysis, Error> {
+    check_board(board)?;
+    let cards = CardSet::try_from_cards(hand.into_iter().chain(board.iter().copied()))?;
+    let (hand_cards, board_cards) = (
+        CardSet::from_iter(hand),
+        CardSet::from_iter(board.iter().copied()),
+    );
+
+    let made_hand = cards.highest_hand();
+    // The flop and turn can still improve
+    let cards_to_come = matches!(board.len(), 3 | 4);
+
+    let (mut flush_draw, mut backdoor_flush_draw) = (false, false);
+    for (cards, hand_cards) in cards
+        .color_bitmaps()
+        .into_iter()
+        .zip(hand_cards.color_bitmaps())
+    {
+        if hand_cards == 0 {
+            continue;
+        }
+        flush_draw |= cards_to_come && cards.count_ones() == 4;
+        backdoor_flush_draw |= board.len() == 3 && cards.count_ones() == 3;
+    }
+
+    let values = all_values(cards);
+    let board_values = all_values(board_cards);
+    let completes_straight =
+        |added: u16| is_straight(values | added) && !is_straight(board_values | added);
+
+    // The values that complete a straight on their own
+    let completing = if cards_to_come && !is_straight(values) {
+        (0..13)
+            .map(|value| 1 << value)
+            .filter(|&value| completes_straight(value))
+            .fold(0, |acc, value| acc | value)
+    } else {
+        0
+    };
+    let straight_draw = match completing.count_ones() {
+        0 => StraightDraw::None,
+        1 => StraightDraw::Gutshot,
+        _ => StraightDraw::OpenEnded,
+    };
+    let backdoor_straight_draw = board.len() == 3
+        && !is_straight(values)
+        && (0..13).any(|first| {
+            (first + 1..13).any(|second| {
+                let added = 1 << first | 1 << second;
+                added & (values | completing) == 0 && completes_straight(added)
+            })
+        });
+
+    let overcards = if board_cards.is_empty() {
+        0
+    } else {
+        let highest = ValueBitmap::<MultiColored>::new(board_values).highest();
+        hand.iter()
+            .filter(|card| card.value().index() > highest)
+            .count() as u8
+    };
+
+    let outs = if cards_to_come {
+        (!cards)
+            .iter()
+            .filter(|&card| {
+                let improved = cards.union(CardSet::from_iter([card])).highest_hand();
+                improved > made_hand
+                    && improved > board_cards.union(CardSet::from_iter([card])).highest_hand()
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(Analysis {
+        hand: made_hand,
+        rank: cards.hand_rank(),
+        board_hand: board_cards.highest_hand(),
+        flush_draw,
+        backdoor_flush_draw,
+        straight_draw,
+        backdoor_straight_draw,
+        overcards,
+        outs,
+        texture: board_texture(board),
+    })
+}
+
+/// The texture of the board, all flags are false for an empty board
+pub fn board_texture(board: &[Card]) -> BoardTexture {
+    let cards = CardSet::from_iter(board.iter().copied());
+    if cards.is_empty() {
+        return BoardTexture::default();
+    }
+
+    let color_counts = cards.color_bitmaps().map(u16::count_ones);
+    let colors = color_counts.iter().filter(|&&count| count > 0).count();
+
+    // The ace is also below the two, at bit 0
+    let values = all_values(cards);
+    let extended = values << 1 | values >> 12 & 1;
+
+    BoardTexture {
+        paired: ColorValueBitmaps::from(cards.color_bitmaps())
+            .at_least_pairs()
+            .inner
+            != 0,
+        monotone: colors == 1 && cards.len() > 1,
+        // Two cards of different colors are rainbow, not two tone
+        two_tone: colors == 2 && color_counts.iter().any(|&count| count >= 2),
+        rainbow: color_counts.iter().all(|&count| count <= 1) && cards.len() > 1,
+        connected: extended & extended >> 1 != 0,
+        straight_possible: (0..10).any(|shift| (extended >> shift & 0b11111).count_ones() >= 3),
+        flush_possible: color_counts.iter().any(|&count| count >= 3),
+    }
+}
+
+/// The values present in any color
+fn all_values(cards: CardSet) -> u16 {
+    ColorValueBitmaps::from(cards.color_bitmaps())
+        .all_colors()
+        .inner
+}
+
+fn is_straight(values: u16) -> bool {
+    ValueBitmap::<MultiColored>::new(values)
+        .is_straight()
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Hand,
+        analysis::{StraightDraw, analyze, board_texture},
+        io::parse_cards,
+        tests::hand,
+    };
+
+    #[test]
+    fn flush_draws() {
+        let analysis = analyze(hand("AhKh"), &parse_cards("Qh7h2c").unwrap()).unwrap();
+        assert_eq!(analysis.hand, Hand::HighCard);
+        assert!(analysis.flush_draw && !analysis.backdoor_flush_draw);
+        assert_eq!(analysis.overcards, 2);
+        // A-K-Q needs both the jack and the ten
+        assert_eq!(analysis.straight_draw, StraightDraw::None);
+        assert!(analysis.backdoor_straight_draw);
+        // 9 hearts, and 3 aces and 3 kings for a pair
+        assert_eq!(analysis.outs.len(), 9 + 3 + 3);
+
+        let backdoor = analyze(hand("AhKh"), &parse_cards("Qh7c2d").unwrap()).unwrap();
+        assert!(backdoor.backdoor_flush_draw && !backdoor.flush_draw);
+        assert!(backdoor.backdoor_straight_draw);
+
+        // The board has the flush draw on its own
+        let board_draw = analyze(hand("AcKd"), &parse_cards("Qh7h2h5h").unwrap()).unwrap();
+        assert!(!board_draw.flush_draw);
+        assert!(!board_draw.outs.contains(&"3h".parse().unwrap()));
+    }
+
+    #[test]
+    fn straight_draws() {
+        let open_ended = analyze(hand("9c8d"), &parse_cards("7h6s2c").unwrap()).unwrap();
+        assert_eq!(open_ended.straight_draw, StraightDraw::OpenEnded);
+        assert!(!open_ended.backdoor_straight_draw);
+
+        let double_gutshot = analyze(hand("9c7d"), &parse_cards("Jh8s5c").unwrap()).unwrap();
+        assert_eq!(double_gutshot.straight_draw, StraightDraw::OpenEnded);
+
+        let gutshot = analyze(hand("9c7d"), &parse_cards("8h5s2c").unwrap()).unwrap();
+        assert_eq!(gutshot.straight_draw, StraightDraw::Gutshot);
+
+        // The wheel
+        let wheel = analyze(hand("Ac2d"), &parse_cards("3h4sKc").unwrap()).unwrap();
+        assert_eq!(wheel.straight_draw, StraightDraw::Gutshot);
+
+        // Made straights dont draw to another one
+        let made = analyze(hand("9c8d"), &parse_cards("7h6s5c").unwrap()).unwrap();
+        assert_eq!(
+            (made.hand, made.straight_draw),
+            (Hand::Straight, StraightDraw::None)
+        );
+    }
+
+    #[test]
+    fn river() {
+        let analysis = analyze(hand("AhKh"), &parse_cards("Qh7h2c3d4s").unwrap()).unwrap();
+        assert!(!analysis.flush_draw);
+        assert_eq!(analysis.straight_draw, StraightDraw::None);
+        assert!(analysis.outs.is_empty());
+    }
+
+    #[test]
+    fn textures() {
+        let texture = |board| board_texture(&parse_cards(board).unwrap());
+
+        let monotone = texture("Qh7h2h");
+        assert!(
+            monotone.monotone && monotone.flush_possible && !monotone.two_tone && !monotone.rainbow
+        );
+
+        let two_tone = texture("Qh7h2c");
+        assert!(two_tone.two_tone && !two_tone.flush_possible && !two_tone.connected);
+        assert!(!two_tone.straight_possible);
+
+        let rainbow = texture("9h8d7c");
+        assert!(
+            rainbow.rainbow && rainbow.connected && rainbow.straight_possible && !rainbow.paired
+        );
+
+        let paired = texture("KhKd2c");
+        assert!(paired.paired && !paired.connected);
+
+        // The ace is connected to the two
+        let wheel = texture("Ah2d9c");
+        assert!(wheel.connected && !wheel.straight_possible);
+        assert!(texture("Ah3d5c").straight_possible);
+
+        // The flags dont overlap on two card boards
+        let two_colors = texture("KsKd");
+        assert!(two_colors.rainbow && !two_colors.two_tone && !two_colors.monotone);
+
+        assert_eq!(texture(""), Default::default());
+    }
+}
@@ -0,0 +1,309 @@
+//! Running a file of scenarios, one command line per line
+
+use crate::cli::{CliError, Command, Format, Mode, execute, parse_args};
+use poker_bot_v2::{
+    Error,
+    card_set::CardSet,
+    preflop::{MAX_OPPONENTS, PreflopTable, StartingHand},
+    report::Report,
+};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+    time::Instant,
+};
+
+/// Runs every line of the input (stdin without a path) as a command and writes one record per line,
+/// as JSON lines, or as CSV with a new header whenever the columns change.
+/// Lines that fail, or arent valid UTF-8, become records with an `error` field, empty lines and lines starting with '#' are skipped.
+/// With a preflop table, `equity` lines without a board or dead cards and with at most `MAX_OPPONENTS` opponents
+/// are looked up instead, with the `standard_error` of the looked up equity.
+/// Only lines without a `--mode`, or heads up lines with `--mode exact`, since the table is sampled against more opponents
+pub fn run(
+    input: Option<&Path>,
+    preflop_table: Option<&Path>,
+    format: Format,
+    out: &mut impl Write,
+) -> Result<(), CliError> {
+    let preflop_table = preflop_table.map(PreflopTable::load).transpose()?;
+    let input: Box<dyn BufRead> = match input {
+        Some(path) => Box::new(BufReader::new(File::open(path).map_err(Error::from)?)),
+        None => Box::new(io::stdin().lock()),
+    };
+    run_lines(input, preflop_table.as_ref(), format, out)
+}
+
+fn run_lines(
+    mut input: impl BufRead,
+    preflop_table: Option<&PreflopTable>,
+    format: Format,
+    out: &mut impl Write,
+) -> Result<(), CliError> {
+    let mut writer = RecordWriter::new(format);
+    let mut bytes = Vec::new();
+    let mut number = 0;
+    loop {
+        bytes.clear();
+        if input.read_until(b'\n', &mut bytes).map_err(Error::from)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+
+        // Lines that arent valid UTF-8 only fail on their own, like any other malformed line
+        let record = match str::from_utf8(&bytes) {
+            Ok(line) if line.trim().is_empty() || line.trim().starts_with('#') => continue,
+            Ok(line) => {
+                let start = Instant::now();
+                run_line(line.trim(), preflop_table)
+                    .map(|report| report.with("elapsed_ms", start.elapsed().as_secs_f64() * 1000.))
+            }
+            Err(_) => Err(Error::Io(io::ErrorKind::InvalidData).into()),
+        };
+        writer.write(out, number, record)?;
+        // Stream the records, instead of waiting for the buffer to fill up
+        out.flush()?;
+    }
+}
+
+fn run_line(line: &str, preflop_table: Option<&PreflopTable>) -> Result<Report, CliError> {
+    let mut args = split_line(line)?;
+    // Scenarios without a command are equities
+    if args.first().is_some_and(|arg| arg.starts_with("--")) {
+        args.insert(0, "equity".to_string());
+    }
+
+    let parsed = parse_args(&args)?;
+    if parsed.format != Format::Text {
+        return Err(CliError::Usage(
+            "--output can only be given to the batch".to_string(),
+        ));
+    }
+
+    match (parsed.command, preflop_table) {
+        (
+            Command::Equity {
+                hand,
+                board,
+                dead,
+                opponents,
+                mode: mode @ (None | Some(Mode::Exact)),
+                ..
+            },
+            Some(table),
+        ) if board.is_empty()
+            && dead.is_empty()
+            && opponents <= MAX_OPPONENTS
+            && (mode.is_none() || opponents == 1) =>
+        {
+            CardSet::try_from_cards(hand)?;
+            let starting_hand = StartingHand::from_cards(hand);
+            Ok(Report::new()
+                .with("hand", format!("{} {}", hand[0], hand[1]))
+                .with("board", "")
+                .with("dead", "")
+                .with("opponents", opponents)
+                .with("starting_hand", starting_hand.to_string())
+                .with("equity", table.vs_random(starting_hand, opponents)?)
+                .with(
+                    "standard_error",
+                    table.standard_error(starting_hand, opponents)?,
+                ))
+        }
+        (Command::Batch { .. } | Command::Interactive | Command::Help, _) => Err(CliError::Usage(
+            "Only equity, evaluate, range-equity and odds can be used in a batch".to_string(),
+        )),
+        (command, _) => execute(&command, &mut io::sink()),
+    }
+}
+
+/// Splits the line at whitespace outside of double quotes, and removes the quotes
+fn split_line(line: &str) -> Result<Vec<String>, CliError> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut quoted = false;
+
+    for char in line.chars() {
+        match char {
+            '"' => {
+                quoted = !quoted;
+                // Quotes can make an empty argument
+                arg.get_or_insert_default();
+            }
+            char if char.is_whitespace() && !quoted => args.extend(arg.take()),
+            char => arg.get_or_insert_default().push(char),
+        }
+    }
+
+    if quoted {
+        return Err(CliError::Usage("Missing the closing quote".to_string()));
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
+/// Writes the records in the format, and keeps track of the current CSV columns
+struct RecordWriter {
+    format: Format,
+    columns: Vec<String>,
+}
+impl RecordWriter {
+    fn new(format: Format) -> Self {
+        Self {
+            format,
+            columns: Vec::new(),
+        }
+    }
+    fn write(
+        &mut self,
+        out: &mut impl Write,
+        line: usize,
+        record: Result<Report, CliError>,
+    ) -> io::Result<()> {
+        if self.format != Format::Csv {
+            let mut report = Report::new().with("line", line);
+            match record {
+                Ok(record) => report.extend("", record),
+                Err(error) => report.push("error", error.to_string()),
+            }
+            return writeln!(out, "{}", report.to_json());
+        }
+
+        let report = match record {
+            Ok(record) => {
+                let mut report = Report::new().with("line", line).with("error", "");
+                report.extend("", record);
+                report
+            }
+            // Keep the current columns, and leave them empty
+            Err(error) => {
+                let mut report = Report::new();
+                for column in &self.columns {
+                    report.push(column.as_str(), "");
+                }
+                report.with("line", line).with("error", error.to_string())
+            }
+        };
+
+        let columns: Vec<&String> = report.fields().iter().map(|(name, _)| name).collect();
+        if columns != self.columns.iter().collect::<Vec<_>>() {
+            self.columns = columns.into_iter().cloned().collect();
+            writeln!(out, "{}", report.csv_header())?;
+        }
+        writeln!(out, "{}", report.csv_row())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        batch::{RecordWriter, run_line, run_lines, split_line},
+        cli::{CliError, Format},
+    };
+    use poker_bot_v2::{
+        preflop::{MAX_OPPONENTS, PreflopTable, StartingHand},
+        report::{Report, Value},
+    };
+
+    fn write(format: Format, records: Vec<Result<Report, CliError>>) -> String {
+        let mut writer = RecordWriter::new(format);
+        let mut out = Vec::new();
+        for (line, record) in records.into_iter().enumerate() {
+            writer.write(&mut out, line + 1, record).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(
+            split_line(r#"range-equity --hero "QQ+, AKs" --board """#).unwrap(),
+            ["range-equity", "--hero", "QQ+, AKs", "--board", ""]
+        );
+        assert!(split_line(r#"--hand "AsKs"#).is_err());
+
+        let report = run_line("--hand AsAd --board Kh7c2d9s3h", None).unwrap();
+        assert_eq!(report.fields()[0].0, "hand");
+        assert!(report.get("equity").is_some());
+
+        assert!(matches!(
+            run_line("--hand AsAd --board Kh7c2d9s3h --output csv", None),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(run_line("batch", None), Err(CliError::Usage(_))));
+        assert!(matches!(
+            run_line("--hand AsAs", None),
+            Err(CliError::Input(_))
+        ));
+
+        // Preflop lookups
+        // A table of zeros, since generating one takes hours
+        let size = 2 * StartingHand::COUNT * (StartingHand::COUNT + 2 * MAX_OPPONENTS);
+        let bytes = [b"PFEQ\x02".as_slice(), &vec![0; size]].concat();
+        let table = PreflopTable::read_from(&bytes[..]).unwrap();
+        let report = run_line("--hand AsKd --opponents 3", Some(&table)).unwrap();
+        assert_eq!(
+            report.get("starting_hand"),
+            Some(&Value::Text("AKo".to_string()))
+        );
+        assert_eq!(report.get("standard_error"), Some(&Value::Float(0.)));
+        let report = run_line("--hand AsKd --mode exact", Some(&table)).unwrap();
+        assert!(report.get("starting_hand").is_some());
+        assert!(matches!(
+            run_line("--hand AsAs", Some(&table)),
+            Err(CliError::Input(_))
+        ));
+        // The table doesnt know about dead cards
+        let report = run_line("--hand AsKd --board Kh7c2d9s3h --dead Ac", Some(&table)).unwrap();
+        assert_eq!(report.get("starting_hand"), None);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut out = Vec::new();
+        let input: &[u8] =
+            b"--hand AsAd --board Kh7c2d9s3h\n\xff\xfe\n\n--hand KsKd --board Kh7c2d9s3h\n";
+        run_lines(input, None, Format::Json, &mut out).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3, "{out}");
+        assert_eq!(
+            lines[1],
+            r#"{"line":2,"error":"Failed to read input: invalid data"}"#
+        );
+        assert!(lines[2].starts_with(r#"{"line":4,"hand":"Ks Kd""#), "{out}");
+    }
+
+    #[test]
+    fn records() {
+        let records = || {
+            vec![
+                Err(CliError::Usage("Unknown flag --x".to_string())),
+                Ok(Report::new().with("equity", 0.5)),
+                Err(CliError::Usage("Missing --hand".to_string())),
+                Ok(Report::new().with("equity", 0.25).with("samples", 10_u64)),
+            ]
+        };
+
+        assert_eq!(
+            write(Format::Json, records()),
+            r#"{"line":1,"error":"Unknown flag --x"}
+{"line":2,"equity":0.5}
+{"line":3,"error":"Missing --hand"}
+{"line":4,"equity":0.25,"samples":10}
+"#
+        );
+        assert_eq!(
+            write(Format::Csv, records()),
+            "line,error
+1,Unknown flag --x
+line,error,equity
+2,,0.5
+3,Missing --hand,
+line,error,equity,samples
+4,,0.25,10
+"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use crate::{Card, CardValue, Color, Error, Hand, HandRank, highest_hand};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Sub, SubAssign};
+
+/// A set of cards, with one bit per card.
+/// Every color has its own 16 bit lane with one bit per `CardValue`,
+/// so the lanes are exactly the `ValueBitmap`s of the colors
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CardSet(u64);
+impl CardSet {
+    pub const EMPTY: Self = Self(0);
+    /// All 52 cards
+    pub const FULL: Self = Self(0x1fff_1fff_1fff_1fff);
+
+    pub const fn new() -> Self {
+        Self::EMPTY
+    }
+    const fn bit(card: Card) -> u64 {
+        1 << (card.color().index() * 16 + card.value().index())
+    }
+    pub const fn contains(self, card: Card) -> bool {
+        self.0 & Self::bit(card) != 0
+    }
+    /// Returns whether the card wasnt in the set yet
+    pub fn insert(&mut self, card: Card) -> bool {
+        let inserted = !self.contains(card);
+        self.0 |= Self::bit(card);
+        inserted
+    }
+    /// Returns whether the card was in the set
+    pub fn remove(&mut self, card: Card) -> bool {
+        let removed = self.contains(card);
+        self.0 &= !Self::bit(card);
+        removed
+    }
+    pub const fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+    /// All cards of the deck that arent in the set
+    pub const fn complement(self) -> Self {
+        Self::FULL.difference(self)
+    }
+    pub const fn is_disjoint(self, other: Self) -> bool {
+        self.0 & other.0 == 0
+    }
+    pub const fn is_subset(self, other: Self) -> bool {
+        self.0 & !other.0 == 0
+    }
+    /// The cards, ordered by color and then by value
+    pub fn iter(self) -> Iter {
+        Iter(self.0)
+    }
+    /// Collects the cards into a set, returns an error on the first duplicate card
+    pub fn try_from_cards(cards: impl IntoIterator<Item = Card>) -> Result<Self, Error> {
+        let mut set = Self::new();
+        for card in cards {
+            if !set.insert(card) {
+                return Err(Error::DuplicateCard(card));
+            }
+        }
+        Ok(set)
+    }
+    /// One `ValueBitmap` per color
+    pub(crate) const fn color_bitmaps(self) -> [u16; 4] {
+        [
+            self.0 as u16,
+            (self.0 >> 16) as u16,
+            (self.0 >> 32) as u16,
+            (self.0 >> 48) as u16,
+        ]
+    }
+    /// The inverse of `color_bitmaps`, the bitmaps may only use the lower 13 bits
+    pub(crate) const fn from_color_bitmaps(bitmaps: [u16; 4]) -> Self {
+        Self(
+            bitmaps[0] as u64
+                | (bitmaps[1] as u64) << 16
+                | (bitmaps[2] as u64) << 32
+                | (bitmaps[3] as u64) << 48,
+        )
+    }
+    /// See `highest_hand::highest_hand`
+    pub fn highest_hand(self) -> Hand {
+        highest_hand::highest_hand_from_bitmaps(self.color_bitmaps())
+    }
+    /// The rank of the cards, which may be at most seven.
+    /// Uses the same evaluator as `evaluate`
+    pub fn hand_rank(self) -> HandRank {
+        debug_assert!(self.len() <= 7);
+
+        // The tables only cover complete hands
+        #[cfg(feature = "lookup-table")]
+        if self.len() == 7 {
+            return crate::lookup_table::hand_rank_from_bitmaps(self.color_bitmaps());
+        }
+
+        highest_hand::hand_rank_from_bitmaps(self.color_bitmaps())
+    }
+}
+
+impl<const N: usize> TryFrom<[Card; N]> for CardSet {
+    type Error = Error;
+
+    fn try_from(cards: [Card; N]) -> Result<Self, Self::Error> {
+        Self::try_from_cards(cards)
+    }
+}
+impl<const N: usize> TryFrom<CardSet> for [Card; N] {
+    type Error = Error;
+
+    fn try_from(set: CardSet) -> Result<Self, Self::Error> {
+        if set.len() != N {
+            return Err(Error::WrongCardCount {
+                min: N,
+                max: N,
+                found: set.len(),
+            });
+        }
+
+        let mut cards = set.iter();
+        Ok(std::array::from_fn(|_| {
+            cards.next().expect("set.len() == N")
+        }))
+    }
+}
+impl FromIterator<Card> for CardSet {
+    fn from_iter<T: IntoIterator<Item = Card>>(cards: T) -> Self {
+        let mut set = Self::new();
+        for card in cards {
+            set.insert(card);
+        }
+        set
+    }
+}
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = Iter;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl BitOr for CardSet {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+impl BitOrAssign for CardSet {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+impl BitAnd for CardSet {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+impl BitAndAssign for CardSet {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = self.intersection(rhs);
+    }
+}
+impl Sub for CardSet {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(rhs)
+    }
+}
+impl SubAssign for CardSet {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = self.difference(rhs);
+    }
+}
+impl Not for CardSet {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        self.complement()
+    }
+}
+
+/// Iterator over the cards of a `CardSet`, from the lowest bit to the highest
+#[derive(Clone)]
+pub struct Iter(u64);
+impl Iterator for Iter {
+    type Item = Card;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let bit = self.0.trailing_zeros() as u8;
+        // Clear the lowest bit
+        self.0 &= self.0 - 1;
+
+        Some(Card::new(
+            CardValue::ALL[(bit % 16) as usize],
+            Color::ALL[(bit / 16) as usize],
+        ))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+impl ExactSizeIterator for Iter {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Card, CardValue, Color, Error, Hand, card_set::CardSet, evaluate};
+
+    const ACE_OF_SPADES: Card = Card::new(CardValue::Ace, Color::Spades);
+    const TWO_OF_HEARTS: Card = Card::new(CardValue::Two, Color::Hearts);
+
+    #[test]
+    fn full_deck() {
+        let cards: Vec<Card> = CardSet::FULL.iter().collect();
+        assert_eq!(cards.len(), 52);
+        assert_eq!(cards.first(), Some(&TWO_OF_HEARTS));
+        assert_eq!(cards.last(), Some(&ACE_OF_SPADES));
+
+        assert_eq!(CardSet::try_from_cards(cards), Ok(CardSet::FULL));
+        assert_eq!(!CardSet::FULL, CardSet::EMPTY);
+    }
+
+    #[test]
+    fn set_algebra() {
+        let mut set = CardSet::new();
+        assert!(set.insert(ACE_OF_SPADES));
+        assert!(!set.insert(ACE_OF_SPADES));
+        assert_eq!(set.len(), 1);
+
+        let other = CardSet::from_iter([TWO_OF_HEARTS]);
+        assert!(set.is_disjoint(other));
+        assert_eq!((set | other).len(), 2);
+        assert!(set.is_subset(set | other));
+        assert_eq!((set | other) - other, set);
+        assert_eq!((set | other) & other, other);
+        assert_eq!((!set).len(), 51);
+        assert!(!(!set).contains(ACE_OF_SPADES));
+
+        assert!(set.remove(ACE_OF_SPADES));
+        assert!(!set.remove(ACE_OF_SPADES));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn duplicates() {
+        assert_eq!(
+            CardSet::try_from([TWO_OF_HEARTS, ACE_OF_SPADES, TWO_OF_HEARTS]),
+            Err(Error::DuplicateCard(TWO_OF_HEARTS))
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        let set = CardSet::try_from([ACE_OF_SPADES, TWO_OF_HEARTS]).unwrap();
+        assert_eq!(
+            <[Card; 2]>::try_from(set),
+            Ok([TWO_OF_HEARTS, ACE_OF_SPADES])
+        );
+        assert_eq!(
+            <[Card; 3]>::try_from(set),
+            Err(Error::WrongCardCount {
+                min: 3,
+                max: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn evaluation() {
+        let cards: [Card; 7] = CardSet::FULL
+            .iter()
+            .filter(|card| card.color() == Color::Clubs)
+            .skip(8)
+            .chain([TWO_OF_HEARTS, ACE_OF_SPADES])
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let set = CardSet::try_from(cards).unwrap();
+
+        assert_eq!(set.highest_hand(), Hand::RoyalFlush);
+        assert_eq!(set.hand_rank(), evaluate(cards));
+    }
+}
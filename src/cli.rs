@@ -0,0 +1,833 @@
+//! Parsing of the command line arguments, and running the parsed commands
+
+use crate::{batch, repl};
+use poker_bot_v2::{
+    Card, Error, Hand, Settings,
+    analysis::{Analysis, StraightDraw, analyze},
+    calculate_distribution, calculate_with,
+    distribution::equity_histogram,
+    io::parse_cards,
+    monte_carlo::{Budget, MIN_SAMPLES, Rng, estimate},
+    multiway::{Opponent, calculate_multiway},
+    range::Range,
+    range_equity::range_vs_range,
+    report::{Report, ToReport},
+};
+use std::{
+    fmt,
+    io::{self, Write},
+    num::NonZeroUsize,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+pub const USAGE: &str = "\
+Usage: poker_bot_v2 [command] [--flag value]... [--output text|json|csv]
+
+Commands:
+  equity        The equity of a hand against random opponents
+                  --hand <cards> [--board <cards>] [--dead <cards>] [--opponents <1-9>] [--threads <n>]
+                  [--mode exact|montecarlo] [--samples <n>] [--time <seconds>] [--seed <n>]
+                The mode is exact against one opponent from the flop on, and montecarlo otherwise
+  evaluate      The made hand, draws and outs of a hand
+                  --hand <cards> [--board <cards>]
+  range-equity  The equity of a range against another range
+                  --hero <range> --villain <range> [--board <cards>] [--dead <cards>]
+  odds          How often both players end up with each hand, and the equity after the next street
+                  --hand <cards> [--board <cards>] [--dead <cards>] [--bins <n>] [--threads <n>]
+                The histogram of --bins needs a board before the river
+  batch         Runs every line of the input as one of the commands above, \"equity\" if it starts with a flag.
+                Writes one JSON (or --output csv) record per line, failed lines get an error field
+                  [--input <path>] [--preflop-table <path>]
+  interactive   Updates the equity, outs and made hand while the cards of a hand come in,
+                with undo, reset and history. The default without a command
+  help          Prints this message
+
+JSON and CSV output include the inputs, derived fields like win_percent, and elapsed_ms.
+Cards are in standard notation, like \"AsKd\" or \"Qh 7c 2d\", ranges like \"QQ+, AKs, KQo:0.5\".
+Dead cards are known to be out of the deck, like folded hands, and can neither come nor be held by an opponent.
+Exit codes: 0 on success, 1 if reading or writing failed, 2 on invalid arguments,
+3 on invalid cards or ranges
+";
+
+/// The number of samples of the Monte Carlo mode without `--samples` or `--time`
+const DEFAULT_SAMPLES: u64 = 1_000_000;
+
+/// Everything that can go wrong when running the binary
+#[derive(Debug, PartialEq, Eq)]
+pub enum CliError {
+    /// Unknown commands or flags, or missing or invalid flag values
+    Usage(String),
+    /// Invalid cards or ranges, or reading the input failed
+    Input(Error),
+    /// Writing the output failed
+    Output(io::ErrorKind),
+}
+impl CliError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Input(Error::Io(_)) | Self::Output(_) => 1,
+            Self::Usage(_) => 2,
+            Self::Input(_) => 3,
+        }
+    }
+}
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(message) => write!(f, "{message}"),
+            Self::Input(error) => write!(f, "{error}"),
+            Self::Output(kind) => write!(f, "Failed to write output: {kind}"),
+        }
+    }
+}
+impl From<Error> for CliError {
+    fn from(error: Error) -> Self {
+        Self::Input(error)
+    }
+}
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        Self::Output(error.kind())
+    }
+}
+
+/// How `equity` calculates the results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Enumerates every opponent hand and remaining board
+    Exact,
+    /// Draws random opponent hands and remaining boards
+    MonteCarlo { budget: Budget, seed: u64 },
+}
+impl Mode {
+    /// Exact against a single opponent from the flop on, where it takes at most seconds.
+    /// Enumerating more opponents or every flop takes minutes to days, so those are estimated
+    pub fn default_for(board: &[Card], opponents: usize) -> Self {
+        if opponents == 1 && board.len() >= 3 {
+            Self::Exact
+        } else {
+            Self::MonteCarlo {
+                budget: Budget::Samples(DEFAULT_SAMPLES),
+                seed: 0,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Equity {
+        hand: [Card; 2],
+        board: Vec<Card>,
+        dead: Vec<Card>,
+        opponents: usize,
+        /// None if the mode wasnt chosen, see `Mode::default_for`
+        mode: Option<Mode>,
+        settings: Settings,
+    },
+    Evaluate {
+        hand: [Card; 2],
+        board: Vec<Card>,
+    },
+    RangeEquity {
+        hero: Range,
+        villain: Range,
+        board: Vec<Card>,
+        dead: Vec<Card>,
+    },
+    Odds {
+        hand: [Card; 2],
+        board: Vec<Card>,
+        dead: Vec<Card>,
+        /// Also calculates the equity histogram of the next street, with this many bins
+        bins: Option<NonZeroUsize>,
+        settings: Settings,
+    },
+    /// Runs every line of the input as a command
+    Batch {
+        /// Stdin without a path
+        input: Option<PathBuf>,
+        preflop_table: Option<PathBuf>,
+    },
+    Interactive,
+    Help,
+}
+
+/// How the results are written
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// For humans
+    #[default]
+    Text,
+    /// A single line JSON object
+    Json,
+    /// A header line with the field names, followed by a line with the values
+    Csv,
+}
+
+/// The parsed command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    pub command: Command,
+    pub format: Format,
+}
+
+/// The flags of a command, `--name value` or `--name=value`
+struct Flags<'a> {
+    flags: Vec<(&'a str, &'a str)>,
+}
+impl<'a> Flags<'a> {
+    fn parse(args: &'a [String]) -> Result<Self, CliError> {
+        let mut flags: Vec<(&str, &str)> = Vec::new();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                return Err(CliError::Usage(format!("Unexpected argument \"{arg}\"")));
+            };
+            let (name, value) = match flag.split_once('=') {
+                Some(flag) => flag,
+                None => match args.next() {
+                    Some(value) if !value.starts_with("--") => (flag, value.as_str()),
+                    _ => return Err(CliError::Usage(format!("Missing the value of --{flag}"))),
+                },
+            };
+
+            if flags.iter().any(|&(other, _)| other == name) {
+                return Err(CliError::Usage(format!(
+                    "--{name} was given more than once"
+                )));
+            }
+            flags.push((name, value));
+        }
+
+        Ok(Self { flags })
+    }
+    /// Removes the flag, so that `finish` only sees the unused ones
+    fn take(&mut self, name: &str) -> Option<&'a str> {
+        let position = self.flags.iter().position(|&(flag, _)| flag == name)?;
+        Some(self.flags.remove(position).1)
+    }
+    fn required(&mut self, name: &str) -> Result<&'a str, CliError> {
+        self.take(name)
+            .ok_or_else(|| CliError::Usage(format!("Missing --{name}")))
+    }
+    /// Parses the value of the flag with `parse`, or returns the default if it wasnt given
+    fn parse_with<T>(
+        &mut self,
+        name: &str,
+        default: T,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Result<T, CliError> {
+        match self.take(name) {
+            Some(value) => parse(value)
+                .ok_or_else(|| CliError::Usage(format!("Invalid value \"{value}\" for --{name}"))),
+            None => Ok(default),
+        }
+    }
+    fn parsed<T: FromStr>(&mut self, name: &str, default: T) -> Result<T, CliError> {
+        self.parse_with(name, default, |value| value.parse().ok())
+    }
+    /// Returns an error if any flag wasnt used by the command
+    fn finish(self) -> Result<(), CliError> {
+        match self.flags.first() {
+            Some((name, _)) => Err(CliError::Usage(format!("Unknown flag --{name}"))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parses the arguments after the binary name.
+/// Without any arguments, the interactive mode is used
+pub fn parse_args(args: &[String]) -> Result<Args, CliError> {
+    let Some((command, args)) = args.split_first() else {
+        return Ok(Args {
+            command: Command::Interactive,
+            format: Format::Text,
+        });
+    };
+    let mut flags = Flags::parse(args)?;
+    let format = flags.parse_with("output", Format::Text, |format| match format {
+        "text" => Some(Format::Text),
+        "json" => Some(Format::Json),
+        "csv" => Some(Format::Csv),
+        _ => None,
+    })?;
+
+    let command = match command.as_str() {
+        "equity" => Command::Equity {
+            hand: parse_hand(flags.required("hand")?)?,
+            board: parse_cards(flags.take("board").unwrap_or_default())?,
+            dead: parse_cards(flags.take("dead").unwrap_or_default())?,
+            opponents: flags.parsed("opponents", 1)?,
+            mode: parse_mode(&mut flags)?,
+            settings: parse_settings(&mut flags)?,
+        },
+        "evaluate" => Command::Evaluate {
+            hand: parse_hand(flags.required("hand")?)?,
+            board: parse_cards(flags.take("board").unwrap_or_default())?,
+        },
+        "range-equity" => Command::RangeEquity {
+            hero: flags.required("hero")?.parse()?,
+            villain: flags.required("villain")?.parse()?,
+            board: parse_cards(flags.take("board").unwrap_or_default())?,
+            dead: parse_cards(flags.take("dead").unwrap_or_default())?,
+        },
+        "odds" => {
+            let hand = parse_hand(flags.required("hand")?)?;
+            let board = parse_cards(flags.take("board").unwrap_or_default())?;
+            let bins = flags.parse_with("bins", None, |bins| bins.parse().ok().map(Some))?;
+            // Checked before anything is calculated, so the distribution isnt written without the histogram
+            if bins.is_some() && board.len() >= 5 {
+                return Err(CliError::Usage(
+                    "--bins needs a board with cards to come".to_string(),
+                ));
+            }
+            Command::Odds {
+                hand,
+                board,
+                dead: parse_cards(flags.take("dead").unwrap_or_default())?,
+                bins,
+                settings: parse_settings(&mut flags)?,
+            }
+        }
+        "batch" => Command::Batch {
+            input: flags
+                .take("input")
+                .filter(|&input| input != "-")
+                .map(PathBuf::from),
+            preflop_table: flags.take("preflop-table").map(PathBuf::from),
+        },
+        "interactive" => Command::Interactive,
+        "help" | "--help" | "-h" => Command::Help,
+        other => return Err(CliError::Usage(format!("Unknown command \"{other}\""))),
+    };
+
+    flags.finish()?;
+    Ok(Args { command, format })
+}
+
+/// Exactly two cards
+fn parse_hand(cards: &str) -> Result<[Card; 2], Error> {
+    let cards = parse_cards(cards)?;
+    let found = cards.len();
+    cards.try_into().map_err(|_| Error::WrongCardCount {
+        min: 2,
+        max: 2,
+        found,
+    })
+}
+
+/// A non-negative integer, also in scientific notation like "1e6"
+fn parse_count(count: &str) -> Option<u64> {
+    count.parse().ok().or_else(|| {
+        let count: f64 = count.parse().ok()?;
+        (count >= 0. && count.fract() == 0. && count <= u64::MAX as f64).then_some(count as u64)
+    })
+}
+
+/// None without `--mode` and any of the Monte Carlo flags
+fn parse_mode(flags: &mut Flags) -> Result<Option<Mode>, CliError> {
+    let (samples, time) = (
+        flags.parse_with("samples", None, |samples| {
+            parse_count(samples)
+                .filter(|&samples| samples >= MIN_SAMPLES)
+                .map(Some)
+        })?,
+        flags.parse_with("time", None, |time| {
+            Duration::try_from_secs_f64(time.parse().ok()?)
+                .ok()
+                .map(Some)
+        })?,
+    );
+    let seed = flags.parse_with("seed", None, |seed| parse_count(seed).map(Some))?;
+
+    let mode = match flags.take("mode") {
+        Some(mode) => mode,
+        None if samples.is_some() || time.is_some() || seed.is_some() => "montecarlo",
+        None => return Ok(None),
+    };
+    match mode {
+        "exact" if samples.is_some() || time.is_some() || seed.is_some() => Err(CliError::Usage(
+            "--samples, --time and --seed only apply to --mode montecarlo".to_string(),
+        )),
+        "exact" => Ok(Some(Mode::Exact)),
+        "montecarlo" => {
+            let budget = match (samples, time) {
+                (Some(_), Some(_)) => {
+                    return Err(CliError::Usage(
+                        "Expected either --samples or --time, not both".to_string(),
+                    ));
+                }
+                (_, Some(time)) => Budget::Time(time),
+                (samples, None) => Budget::Samples(samples.unwrap_or(DEFAULT_SAMPLES)),
+            };
+            Ok(Some(Mode::MonteCarlo {
+                budget,
+                seed: seed.unwrap_or(0),
+            }))
+        }
+        other => Err(CliError::Usage(format!(
+            "Unknown mode \"{other}\", expected exact or montecarlo"
+        ))),
+    }
+}
+
+fn parse_settings(flags: &mut Flags) -> Result<Settings, CliError> {
+    let settings = Settings::default();
+    Ok(Settings {
+        threads: flags.parsed("threads", settings.threads)?,
+        ..settings
+    })
+}
+
+/// Runs the command, writing the results to `out` in the format
+pub fn run(args: &Args, out: &mut impl Write) -> Result<(), CliError> {
+    match &args.command {
+        Command::Help => return Ok(write!(out, "{USAGE}")?),
+        Command::Batch {
+            input,
+            preflop_table,
+        } => return batch::run(input.as_deref(), preflop_table.as_deref(), args.format, out),
+        Command::Interactive if args.format != Format::Text => {
+            return Err(CliError::Usage(
+                "The interactive mode only writes text".to_string(),
+            ));
+        }
+        Command::Interactive => return repl::run(io::stdin().lock(), out),
+        _ => {}
+    }
+
+    let start = Instant::now();
+    let report = match args.format {
+        Format::Text => execute(&args.command, out)?,
+        Format::Json | Format::Csv => execute(&args.command, &mut io::sink())?,
+    }
+    .with("elapsed_ms", start.elapsed().as_secs_f64() * 1000.);
+
+    match args.format {
+        Format::Text => {}
+        Format::Json => writeln!(out, "{}", report.to_json())?,
+        Format::Csv => writeln!(out, "{}\n{}", report.csv_header(), report.csv_row())?,
+    }
+    Ok(())
+}
+
+/// Runs the command, writes the results as text to `text` and returns them as a report
+pub fn execute(command: &Command, text: &mut impl Write) -> Result<Report, CliError> {
+    match command {
+        Command::Equity {
+            hand,
+            board,
+            dead,
+            opponents,
+            mode,
+            settings,
+        } => {
+            let mode = mode.unwrap_or_else(|| Mode::default_for(board, *opponents));
+            equity(*hand, board, dead, *opponents, mode, *settings, text)
+        }
+        Command::Evaluate { hand, board } => evaluate(*hand, board, text),
+        Command::RangeEquity {
+            hero,
+            villain,
+            board,
+            dead,
+        } => {
+            let results = range_vs_range(hero, villain, board, dead)?;
+            writeln!(
+                text,
+                "Hero equity: {} ({} combos)",
+                percent(results.equity()),
+                results.hero.len()
+            )?;
+            writeln!(
+                text,
+                "Villain equity: {} ({} combos)",
+                percent(1. - results.equity()),
+                results.villain.len()
+            )?;
+
+            let mut report = Report::new()
+                .with("hero", hero.to_string())
+                .with("villain", villain.to_string())
+                .with("board", cards(board))
+                .with("dead", cards(dead));
+            report.extend("", results.to_report());
+            Ok(report)
+        }
+        Command::Odds {
+            hand,
+            board,
+            dead,
+            bins,
+            settings,
+        } => odds(*hand, board, dead, *bins, *settings, text),
+        Command::Batch { .. } | Command::Interactive | Command::Help => Err(CliError::Usage(
+            "Only equity, evaluate, range-equity and odds have results".to_string(),
+        )),
+    }
+}
+
+fn equity(
+    hand: [Card; 2],
+    board: &[Card],
+    dead: &[Card],
+    opponents: usize,
+    mode: Mode,
+    settings: Settings,
+    text: &mut impl Write,
+) -> Result<Report, CliError> {
+    let mut report = Report::new()
+        .with("hand", cards(&hand))
+        .with("board", cards(board))
+        .with("dead", cards(dead))
+        .with("opponents", opponents);
+
+    let random_opponents = vec![Opponent::Random; opponents];
+    match mode {
+        Mode::Exact if opponents == 1 => {
+            let results = calculate_with(hand, board, dead, settings)?;
+            writeln!(text, "Equity: {}", percent(results.equity()))?;
+            writeln!(
+                text,
+                "Wins: {}, draws: {}, losses: {}",
+                results.wins(),
+                results.draws(),
+                results.losses()
+            )?;
+            report.extend("", results.to_report());
+        }
+        Mode::Exact => {
+            let results = calculate_multiway(hand, board, dead, &random_opponents)?;
+            let hero = results.hero();
+            writeln!(text, "Equity: {}", percent(hero.equity()))?;
+            writeln!(
+                text,
+                "Wins: {}, ties: {}, losses: {}",
+                hero.wins(),
+                hero.ties(),
+                hero.losses()
+            )?;
+            report.extend("", results.to_report());
+        }
+        Mode::MonteCarlo { budget, seed } => {
+            let estimate = estimate(
+                hand,
+                board,
+                dead,
+                &random_opponents,
+                budget,
+                &mut Rng::new(seed),
+            )?;
+            let (low, high) = estimate.confidence_interval(0, 1.96);
+            writeln!(
+                text,
+                "Equity: {} (95% confidence: {} to {})",
+                percent(estimate.equity(0)),
+                percent(low),
+                percent(high)
+            )?;
+            writeln!(text, "Samples: {}", estimate.samples())?;
+            report.push("seed", seed);
+            report.extend("", estimate.to_report());
+        }
+    }
+    Ok(report)
+}
+
+fn evaluate(hand: [Card; 2], board: &[Card], text: &mut impl Write) -> Result<Report, CliError> {
+    let analysis = analyze(hand, board)?;
+    writeln!(
+        text,
+        "Hand: {} (board: {})",
+        analysis.hand, analysis.board_hand
+    )?;
+
+    writeln!(text, "Draws: {}", list(&draws(&analysis)))?;
+    writeln!(text, "Overcards: {}", analysis.overcards)?;
+    if matches!(board.len(), 3 | 4) {
+        writeln!(
+            text,
+            "Outs ({}): {}",
+            analysis.outs.len(),
+            cards(&analysis.outs)
+        )?;
+    }
+
+    let texture = analysis.texture;
+    let texture: Vec<&str> = [
+        (texture.paired, "paired"),
+        (texture.monotone, "monotone"),
+        (texture.two_tone, "two tone"),
+        (texture.rainbow, "rainbow"),
+        (texture.connected, "connected"),
+        (texture.straight_possible, "straight possible"),
+        (texture.flush_possible, "flush possible"),
+    ]
+    .into_iter()
+    .filter_map(|(present, property)| present.then_some(property))
+    .collect();
+    writeln!(text, "Board texture: {}", list(&texture))?;
+
+    let mut report = Report::new()
+        .with("hand", cards(&hand))
+        .with("board", cards(board));
+    report.extend("", analysis.to_report());
+    Ok(report)
+}
+
+/// The names of the draws of the analysis
+pub fn draws(analysis: &Analysis) -> Vec<&'static str> {
+    [
+        (analysis.flush_draw, "flush draw"),
+        (analysis.backdoor_flush_draw, "backdoor flush draw"),
+        (
+            analysis.straight_draw == StraightDraw::OpenEnded,
+            "open ended straight draw",
+        ),
+        (analysis.straight_draw == StraightDraw::Gutshot, "gutshot"),
+        (analysis.backdoor_straight_draw, "backdoor straight draw"),
+    ]
+    .into_iter()
+    .filter_map(|(present, draw)| present.then_some(draw))
+    .collect()
+}
+
+fn odds(
+    hand: [Card; 2],
+    board: &[Card],
+    dead: &[Card],
+    bins: Option<NonZeroUsize>,
+    settings: Settings,
+    text: &mut impl Write,
+) -> Result<Report, CliError> {
+    let distribution = calculate_distribution(hand, board, dead, settings)?;
+    writeln!(text, "{:<16}{:>9}{:>9}", "Hand", "Hero", "Opponent")?;
+    for category in Hand::ALL {
+        writeln!(
+            text,
+            "{category:<16}{:>9}{:>9}",
+            percent(distribution.hero_frequency(category)),
+            percent(distribution.opponent_frequency(category))
+        )?;
+    }
+    writeln!(text, "Equity: {}", percent(distribution.results().equity()))?;
+
+    let mut report = Report::new()
+        .with("hand", cards(&hand))
+        .with("board", cards(board))
+        .with("dead", cards(dead));
+    report.extend("", distribution.to_report());
+
+    if let Some(bins) = bins {
+        let histogram = equity_histogram(hand, board, dead, bins, settings)?;
+        writeln!(
+            text,
+            "Next street ({} runouts): E[HS] {:.4}, E[HS²] {:.4}",
+            histogram.runouts(),
+            histogram.expected_hand_strength(),
+            histogram.expected_squared_hand_strength()
+        )?;
+        let width = 100. / bins.get() as f64;
+        for (bin, &runouts) in histogram.bins().iter().enumerate() {
+            writeln!(
+                text,
+                "{:>5.1}% - {:>5.1}%: {}",
+                bin as f64 * width,
+                (bin + 1) as f64 * width,
+                percent(runouts as f64 / histogram.runouts() as f64)
+            )?;
+        }
+        report.extend("next_street_", histogram.to_report());
+    }
+    Ok(report)
+}
+
+/// Standard notation, separated by spaces
+pub fn cards(cards: &[Card]) -> String {
+    let cards: Vec<String> = cards.iter().map(Card::to_string).collect();
+    cards.join(" ")
+}
+
+pub fn percent(share: f64) -> String {
+    format!("{:.2}%", share * 100.)
+}
+
+/// Comma separated, or "none"
+pub fn list(items: &[&str]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{Args, CliError, Command, Format, Mode, parse_args, run};
+    use poker_bot_v2::{Error, Settings, io::parse_cards, monte_carlo::Budget};
+    use std::time::Duration;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn output(command: &str) -> String {
+        let mut out = Vec::new();
+        run(&parse_args(&args(command)).unwrap(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_args(&args(
+                "equity --hand AsKs --board Qh7c2d --dead 5s --opponents 2 --mode montecarlo --samples 1e6"
+            ))
+            .map(|args| args.command),
+            Ok(Command::Equity {
+                hand: [parse_cards("As").unwrap()[0], parse_cards("Ks").unwrap()[0]],
+                board: parse_cards("Qh7c2d").unwrap(),
+                dead: parse_cards("5s").unwrap(),
+                opponents: 2,
+                mode: Some(Mode::MonteCarlo {
+                    budget: Budget::Samples(1_000_000),
+                    seed: 0
+                }),
+                settings: Settings::default(),
+            })
+        );
+        assert!(matches!(
+            parse_args(&args("equity --hand=AsKs --mode=montecarlo --time=0.5")),
+            Ok(Args {
+                command: Command::Equity {
+                mode: Some(Mode::MonteCarlo {
+                    budget: Budget::Time(time),
+                    ..
+                }),
+                    ..
+                },
+                format: Format::Text,
+            }) if time == Duration::from_millis(500)
+        ));
+
+        // Without --mode, the Monte Carlo flags choose it, and otherwise the spot does
+        let mode = |command| match parse_args(&args(command)).unwrap().command {
+            Command::Equity { mode, .. } => mode,
+            _ => panic!(),
+        };
+        assert!(matches!(
+            mode("equity --hand AsKs --seed 3"),
+            Some(Mode::MonteCarlo { seed: 3, .. })
+        ));
+        assert_eq!(mode("equity --hand AsKs --board Qh7c2d"), None);
+        let board = parse_cards("Qh7c2d").unwrap();
+        assert_eq!(Mode::default_for(&board, 1), Mode::Exact);
+        assert!(matches!(
+            Mode::default_for(&board, 2),
+            Mode::MonteCarlo { .. }
+        ));
+        assert!(matches!(Mode::default_for(&[], 1), Mode::MonteCarlo { .. }));
+
+        assert_eq!(
+            parse_args(&[]),
+            Ok(Args {
+                command: Command::Interactive,
+                format: Format::Text
+            })
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let exit_code = |command| parse_args(&args(command)).unwrap_err().exit_code();
+
+        // Usage
+        assert_eq!(exit_code("fold"), 2);
+        assert_eq!(exit_code("equity --hand AsKs --color blue"), 2);
+        assert_eq!(exit_code("equity --hand"), 2);
+        assert_eq!(exit_code("equity --board Qh7c2d"), 2);
+        assert_eq!(exit_code("equity --hand AsKs --mode exact --samples 10"), 2);
+        assert_eq!(exit_code("equity --hand AsKs --samples 0"), 2);
+        assert_eq!(exit_code("equity --hand AsKs --samples 1"), 2);
+        assert_eq!(exit_code("equity --hand AsKs --opponents two"), 2);
+        assert_eq!(exit_code("odds --hand AsKs --bins 0"), 2);
+        assert_eq!(exit_code("odds --hand AsKs --board Kh7c2d9s3h --bins 4"), 2);
+        assert_eq!(exit_code("evaluate --hand AsKs --output xml"), 2);
+
+        // Input
+        assert_eq!(
+            parse_args(&args("evaluate --hand AsKsQs")),
+            Err(CliError::Input(Error::WrongCardCount {
+                min: 2,
+                max: 2,
+                found: 3
+            }))
+        );
+        assert_eq!(exit_code("range-equity --hero QQ+ --villain XX"), 3);
+        assert_eq!(exit_code("evaluate --hand AsXs"), 3);
+    }
+
+    #[test]
+    fn commands() {
+        let equity = output("equity --hand AsAd --board Kh7c2d9s3h");
+        assert!(equity.starts_with("Equity: "), "{equity}");
+
+        let estimate = output("equity --hand AsAd --opponents 3 --mode montecarlo --samples 1000");
+        assert!(estimate.contains("Samples: 1000"), "{estimate}");
+
+        let evaluation = output("evaluate --hand AhKh --board Qh7h2c");
+        assert!(evaluation.contains("Hand: High card"), "{evaluation}");
+        assert!(evaluation.contains("flush draw"), "{evaluation}");
+        assert!(evaluation.contains("Outs (15)"), "{evaluation}");
+
+        let odds = output("odds --hand AhQh --board Kh7h2d5c --bins 4");
+        assert!(odds.contains("Flush"), "{odds}");
+        assert!(odds.contains("(46 runouts)"), "{odds}");
+
+        let json = output("equity --hand AsAd --board Kh7c2d9s3h --output json");
+        assert!(
+            json.starts_with(
+                r#"{"hand":"As Ad","board":"Kh 7c 2d 9s 3h","dead":"","opponents":1,"wins":"#
+            ),
+            "{json}"
+        );
+        assert!(json.contains(r#""elapsed_ms":"#), "{json}");
+
+        let csv = output("range-equity --hero AA --villain KK --board Qh7c2d9s --output csv");
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(
+            lines[0].starts_with("hero,villain,board,dead,hero_equity,villain_equity"),
+            "{csv}"
+        );
+        assert!(lines[1].starts_with("AA,KK,Qh 7c 2d 9s,,0.9"), "{csv}");
+
+        // The dead cards leave the opponent C(41, 2) hands on the river
+        let dead = output("equity --hand AsAd --board Kh7c2d9s3h --dead QhQdQcQs --output json");
+        assert!(dead.contains(r#""dead":"Qh Qd Qc Qs","#), "{dead}");
+        assert!(dead.contains(r#""total":820,"#), "{dead}");
+
+        let mut out = Vec::new();
+        assert_eq!(
+            run(
+                &parse_args(&args("equity --hand AsAd --board AsKd2c")).unwrap(),
+                &mut out
+            ),
+            Err(CliError::Input(Error::DuplicateCard(
+                parse_cards("As").unwrap()[0]
+            )))
+        );
+        assert_eq!(
+            run(
+                &parse_args(&args("odds --hand AsAd --board Kd7c2c --dead 2c")).unwrap(),
+                &mut out
+            ),
+            Err(CliError::Input(Error::DuplicateCard(
+                parse_cards("2c").unwrap()[0]
+            )))
+        );
+    }
+}
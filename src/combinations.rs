@@ -1,40 +1,92 @@
-use std::{array, ops::Index};
+use std::ops::{Deref, Index, IndexMut};
 
-use crate::Hand;
+use crate::HandRank;
 
-/// An iterator that returns all possible length R combinations of 0..N
-#[derive(Clone)]
-pub struct Combinations<const N: usize, const R: usize> {
-    indices: [usize; R],
-    first: bool,
+/// The most indices a combination can have (two hand cards and five board cards)
+pub const MAX_R: usize = 7;
+
+/// Up to `MAX_R` indices in ascending order, derefs to a slice of them
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Combination {
+    indices: [usize; MAX_R],
+    len: usize,
 }
-impl<const N: usize, const R: usize> Default for Combinations<N, R> {
-    fn default() -> Self {
-        Self::new()
+impl Deref for Combination {
+    type Target = [usize];
+    fn deref(&self) -> &Self::Target {
+        &self.indices[..self.len]
     }
 }
-impl<const N: usize, const R: usize> Combinations<N, R> {
-    pub fn new() -> Self {
-        assert!(R <= N);
+impl Combination {
+    /// Maps every index to `mapping[index]`
+    pub fn map_indices(&self, mapping: &[usize]) -> Self {
+        let mut mapped = *self;
+        for index in &mut mapped.indices[..self.len] {
+            *index = mapping[*index];
+        }
+        mapped
+    }
+}
+
+/// An iterator that returns all possible length r combinations of 0..n
+#[derive(Clone)]
+pub struct Combinations {
+    n: usize,
+    combination: Combination,
+    first: bool,
+}
+impl Combinations {
+    pub fn new(n: usize, r: usize) -> Self {
+        assert!(r <= n);
+        assert!(r <= MAX_R);
 
         Self {
-            indices: array::from_fn(|index| index),
+            n,
+            combination: Combination {
+                indices: std::array::from_fn(|index| index),
+                len: r,
+            },
             first: true,
         }
     }
-}
-impl<const N: usize, const R: usize> Iterator for Combinations<N, R> {
-    type Item = [usize; R];
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Starts at the combination at the given position in lexicographic order,
+    /// so a range of positions can be iterated on its own
+    pub fn starting_at(n: usize, r: usize, position: usize) -> Self {
+        let mut combinations = Self::new(n, r);
+        combinations.combination = unrank(n, r, position);
+        combinations
+    }
+    /// Like `next`, but also returns the position of the leftmost index that changed.
+    /// All indices from there on have changed, the ones before it stayed the same
+    pub fn next_with_changed(&mut self) -> Option<(Combination, usize)> {
         if self.first {
             self.first = false;
-            return Some(self.indices);
+            return Some((self.combination, 0));
         }
 
-        if increment_indices::<N, R>(&mut self.indices) {
-            None
+        let len = self.combination.len;
+        advance_indices(self.n, &mut self.combination.indices[..len])
+            .map(|changed| (self.combination, changed))
+    }
+}
+impl Iterator for Combinations {
+    type Item = Combination;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_changed().map(|(combination, _)| combination)
+    }
+    /// Jumps directly to the combination, instead of advancing n times (which also makes `skip` fast)
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let len = num_combinations(self.n, self.combination.len);
+        let position = rank(self.n, &self.combination) + n + usize::from(!self.first);
+
+        self.first = false;
+        if position < len {
+            self.combination = unrank(self.n, self.combination.len, position);
+            Some(self.combination)
         } else {
-            Some(self.indices)
+            // Leave the last combination, so that `next` returns None from now on
+            self.combination = unrank(self.n, self.combination.len, len - 1);
+            None
         }
     }
 }
@@ -58,77 +110,124 @@ pub const fn num_combinations(n: usize, r: usize) -> usize {
     }
 }
 
-/// Increments the given indices, returns whether the end has been reached
-const fn increment_indices<const N: usize, const R: usize>(indices: &mut [usize; R]) -> bool {
-    let mut indices_index = R - 1;
+/// The position of the combination of 0..n in lexicographic order, the inverse of `unrank`
+pub const fn rank(n: usize, combination: &[usize]) -> usize {
+    let r = combination.len();
+    let mut position = 0;
+
+    let mut index = 0;
+    let mut value = 0;
+    while index < r {
+        // Count all combinations that start with a smaller value at this index
+        while value < combination[index] {
+            position += num_combinations(n - 1 - value, r - 1 - index);
+            value += 1;
+        }
+        value += 1;
+        index += 1;
+    }
+    position
+}
+
+/// The length r combination of 0..n at the given position in lexicographic order, the inverse of `rank`
+pub const fn unrank(n: usize, r: usize, mut position: usize) -> Combination {
+    assert!(r <= MAX_R);
+    assert!(position < num_combinations(n, r));
+
+    let mut combination = Combination {
+        indices: [0; MAX_R],
+        len: r,
+    };
+    // Same indices as a new `Combinations` after `r`, so equal combinations compare equal
+    let mut index = r;
+    while index < MAX_R {
+        combination.indices[index] = index;
+        index += 1;
+    }
+
+    let mut index = 0;
+    let mut value = 0;
+    while index < r {
+        // Skip all combinations that start with a smaller value at this index
+        loop {
+            let skipped = num_combinations(n - 1 - value, r - 1 - index);
+            if position < skipped {
+                break;
+            }
+            position -= skipped;
+            value += 1;
+        }
+        combination.indices[index] = value;
+        value += 1;
+        index += 1;
+    }
+    combination
+}
+
+/// Increments the given indices (combinations of 0..n), returns the position of the leftmost
+/// changed index, or None if the end has been reached
+const fn advance_indices(n: usize, indices: &mut [usize]) -> Option<usize> {
+    let r = indices.len();
+    // The only combination without any indices
+    if r == 0 {
+        return None;
+    }
+
+    let mut indices_index = r - 1;
 
     // Decrement indices index to find one that isnt at its maximum allowed value
-    while indices[indices_index] == indices_index + N - R {
+    while indices[indices_index] == indices_index + n - r {
         if indices_index > 0 {
             indices_index -= 1;
         } else {
             // Last combination reached
-            return true;
+            return None;
         }
     }
 
     // Increment the found index
     indices[indices_index] += 1;
     // And reset the ones to its right
-    // Cannot use `for right_index in indices_index + 1..R` because it isnt const yet
+    // Cannot use `for right_index in indices_index + 1..r` because it isnt const yet
     let mut right_index = indices_index + 1;
-    while right_index < R {
+    while right_index < r {
         indices[right_index] = indices[right_index - 1] + 1;
 
         right_index += 1;
     }
 
-    false
+    Some(indices_index)
 }
 
-pub struct CombinationMap<const N: usize, const R: usize>
-where
-    [(); num_combinations(N, R)]:,
-    [[(); R - 1]; N - 1]:,
-{
+/// Maps every length r combination of 0..n to a `HandRank`
+pub struct CombinationMap {
+    n: usize,
+    r: usize,
     /// An array containing the player hand, in lexicographic order by the combination that produced it
-    pub array: [Hand; num_combinations(N, R)],
-    precomputed_num_combinations: [[usize; R - 1]; N - 1],
+    pub array: Vec<HandRank>,
+    /// nCr for all n' < n and r' < r, indexed by `n' * r + r'`
+    precomputed_num_combinations: Vec<usize>,
 }
-impl<const N: usize, const R: usize> Default for CombinationMap<N, R>
-where
-    [(); num_combinations(N, R)]:,
-    [[(); R - 1]; N - 1]:,
-{
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl<const N: usize, const R: usize> CombinationMap<N, R>
-where
-    [(); num_combinations(N, R)]:,
-    [[(); R - 1]; N - 1]:,
-{
-    pub const fn new() -> Self {
+impl CombinationMap {
+    pub fn new(n: usize, r: usize) -> Self {
         Self {
-            array: [Hand::RoyalFlush; num_combinations(N, R)],
-            precomputed_num_combinations: precompute_num_combinations::<N, R>(),
+            n,
+            r,
+            array: vec![HandRank::MIN; num_combinations(n, r)],
+            precomputed_num_combinations: (0..n)
+                .flat_map(|n| (0..r).map(move |r| num_combinations(n, r)))
+                .collect(),
         }
     }
-}
-impl<const N: usize, const R: usize> Index<[usize; R]> for CombinationMap<N, R>
-where
-    [(); num_combinations(N, R)]:,
-    [[(); R - 1]; N - 1]:,
-{
-    type Output = Hand;
-    fn index(&self, combination_index: [usize; R]) -> &Self::Output {
+    /// The position of the combination in lexicographic order
+    fn position(&self, combination: &[usize]) -> usize {
+        debug_assert_eq!(combination.len(), self.r);
+
         let mut index = 0;
 
-        for (position, value) in combination_index.into_iter().enumerate() {
+        for (position, &value) in combination.iter().enumerate() {
             let min_value = if position > 0 {
-                combination_index[position - 1] + 1
+                combination[position - 1] + 1
             } else {
                 0
             };
@@ -136,42 +235,37 @@ where
             for smaller_value in min_value..value {
                 // TODO: Maybe invert this nested array, so that the index changing
                 // more frequently (smaller_value) is the inner one (better for cache)
-                index += self.precomputed_num_combinations[N - 1 - smaller_value][R - 1 - position];
+                index += self.precomputed_num_combinations
+                    [(self.n - 1 - smaller_value) * self.r + self.r - 1 - position];
             }
         }
 
-        &self.array[index]
+        index
     }
 }
-
-const fn precompute_num_combinations<const N: usize, const R: usize>() -> [[usize; R - 1]; N - 1] {
-    let mut result = [[0; R - 1]; N - 1];
-
-    let mut n = 0;
-    while n < N - 1 {
-        let mut r = 0;
-        while r < R - 1 {
-            result[n][r] = num_combinations(n, r);
-
-            r += 1;
-        }
-
-        n += 1
+impl Index<&[usize]> for CombinationMap {
+    type Output = HandRank;
+    fn index(&self, combination: &[usize]) -> &Self::Output {
+        &self.array[self.position(combination)]
+    }
+}
+impl IndexMut<&[usize]> for CombinationMap {
+    fn index_mut(&mut self, combination: &[usize]) -> &mut Self::Output {
+        let position = self.position(combination);
+        &mut self.array[position]
     }
-
-    result
 }
 
 #[cfg(test)]
 mod tests {
-    mod increment_indices {
+    mod advance_indices {
         use std::array;
 
-        use crate::combinations::increment_indices;
+        use crate::combinations::{Combination, Combinations, advance_indices};
 
         #[test]
         fn basic() {
-            macro_rules! increment_indices {
+            macro_rules! advance_indices {
                 ($N:literal,$R:literal,$expected:expr) => {
                     // I know this is just manual snapshot testing, but insta kinda doesnt play well with loops & macros
                     let mut indices: [usize; $R] = array::from_fn(|index| index);
@@ -182,14 +276,15 @@ mod tests {
                         assert_eq!(indices, $expected[i]);
                         i += 1;
 
-                        if !increment_indices::<$N, $R>(&mut indices) { break; }
+                        if advance_indices($N, &mut indices).is_none() { break; }
                     }
+                    assert_eq!(i, $expected.len());
                 };
             }
 
             // I know this is just manual snapshot testing, but insta kinda doesnt play well with loops & macros
-            increment_indices!(4, 2, [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]]);
-            increment_indices!(5, 3, [
+            advance_indices!(4, 2, [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]]);
+            advance_indices!(5, 3, [
                 [0, 1, 2],
                 [0, 1, 3],
                 [0, 1, 4],
@@ -202,6 +297,77 @@ mod tests {
                 [2, 3, 4],
             ]);
         }
+
+        #[test]
+        fn changed_positions() {
+            let mut combinations = Combinations::new(5, 3);
+            let mut previous: Option<Combination> = None;
+
+            while let Some((indices, changed)) = combinations.next_with_changed() {
+                if let Some(previous) = previous {
+                    assert_eq!(indices[..changed], previous[..changed]);
+                    assert_ne!(indices[changed], previous[changed]);
+                } else {
+                    assert_eq!(changed, 0);
+                }
+                previous = Some(indices);
+            }
+
+            assert_eq!(previous.as_deref(), Some(&[2, 3, 4][..]));
+        }
+    }
+    mod rank {
+        use crate::combinations::{
+            Combination, CombinationMap, Combinations, num_combinations, rank, unrank,
+        };
+
+        #[test]
+        fn round_trip() {
+            for (n, r) in [(5, 0), (5, 5), (7, 3), (12, 5), (47, 2)] {
+                let map = CombinationMap::new(n, r);
+                for (position, combination) in Combinations::new(n, r).enumerate() {
+                    assert_eq!(rank(n, &combination), position);
+                    assert_eq!(map.position(&combination), position);
+                    assert_eq!(unrank(n, r, position), combination);
+                }
+            }
+        }
+
+        #[test]
+        fn constant() {
+            const RANK: usize = rank(52, &[47, 48, 49, 50, 51]);
+            assert_eq!(RANK, num_combinations(52, 5) - 1);
+            const UNRANK: Combination = unrank(52, 2, 1);
+            assert_eq!(*UNRANK, [0, 2]);
+        }
+
+        #[test]
+        fn nth() {
+            let mut combinations = Combinations::new(10, 3);
+            assert_eq!(combinations.nth(5), Combinations::new(10, 3).nth(5));
+            assert_eq!(combinations.next().as_deref(), Some(&[0, 1, 8][..]));
+            assert_eq!(combinations.nth(2).as_deref(), Some(&[0, 2, 4][..]));
+
+            assert_eq!(combinations.nth(200), None);
+            assert_eq!(combinations.next(), None);
+            assert_eq!(Combinations::new(10, 3).skip(119).count(), 1);
+        }
+    }
+    mod starting_at {
+        use crate::combinations::{Combinations, num_combinations};
+
+        #[test]
+        fn same_as_skip() {
+            for (n, r) in [(5, 0), (5, 5), (7, 3), (12, 5)] {
+                for position in 0..num_combinations(n, r) {
+                    assert!(
+                        Combinations::starting_at(n, r, position)
+                            .eq(Combinations::new(n, r).skip(position)),
+                        "n {n}, r {r}, position {position}"
+                    );
+                }
+            }
+        }
     }
     mod num_combinations {
         use crate::combinations::num_combinations;
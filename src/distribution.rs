@@ -0,0 +1,224 @@
+use crate::{
+    Card, Error, Hand, HandRank, Results, Settings, Showdown, calculate_with, card_set::CardSet,
+    check_deck_size, combinations::Combinations, create_deck_without_present_cards, isomorphism,
+};
+use std::{num::NonZeroUsize, ops::AddAssign};
+
+/// The results of `calculate`, together with how often each player ends up with each `Hand` category
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Distribution {
+    results: Results,
+    /// Indexed by `Hand::strength`
+    hero: [u64; 10],
+    opponent: [u64; 10],
+}
+impl Distribution {
+    /// Records the showdown and the categories of both hands `weight` times
+    pub(crate) fn record(&mut self, hero: HandRank, opponent: HandRank, weight: u64) {
+        self.results.record(Showdown::new(hero, opponent), weight);
+        self.hero[hero.hand().strength() as usize] += weight;
+        self.opponent[opponent.hand().strength() as usize] += weight;
+    }
+    pub fn results(&self) -> Results {
+        self.results
+    }
+    /// The number of showdowns the hero had the category in
+    pub fn hero(&self, hand: Hand) -> u64 {
+        self.hero[hand.strength() as usize]
+    }
+    /// The number of showdowns the opponent had the category in
+    pub fn opponent(&self, hand: Hand) -> u64 {
+        self.opponent[hand.strength() as usize]
+    }
+    /// The share of showdowns the hero had the category in
+    pub fn hero_frequency(&self, hand: Hand) -> f64 {
+        self.hero(hand) as f64 / self.results.total() as f64
+    }
+    /// The share of showdowns the opponent had the category in
+    pub fn opponent_frequency(&self, hand: Hand) -> f64 {
+        self.opponent(hand) as f64 / self.results.total() as f64
+    }
+}
+impl AddAssign for Distribution {
+    fn add_assign(&mut self, other: Self) {
+        self.results += other.results;
+        for (count, other) in self.hero.iter_mut().zip(other.hero) {
+            *count += other;
+        }
+        for (count, other) in self.opponent.iter_mut().zip(other.opponent) {
+            *count += other;
+        }
+    }
+}
+
+/// How the equity of the hero is distributed over the runouts of the next street
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquityHistogram {
+    /// The number of runouts with an equity in each of the equally sized bins from 0 to 1
+    bins: Vec<u64>,
+    runouts: u64,
+    equity_sum: f64,
+    squared_equity_sum: f64,
+}
+impl EquityHistogram {
+    fn new(bins: NonZeroUsize) -> Self {
+        Self {
+            bins: vec![0; bins.get()],
+            runouts: 0,
+            equity_sum: 0.,
+            squared_equity_sum: 0.,
+        }
+    }
+    fn record(&mut self, equity: f64, weight: u64) {
+        let bin = ((equity * self.bins.len() as f64) as usize).min(self.bins.len() - 1);
+        self.bins[bin] += weight;
+        self.runouts += weight;
+        self.equity_sum += equity * weight as f64;
+        self.squared_equity_sum += equity * equity * weight as f64;
+    }
+    /// The number of runouts per bin, the first bin starts at equity 0 and the last one ends at 1
+    pub fn bins(&self) -> &[u64] {
+        &self.bins
+    }
+    pub fn runouts(&self) -> u64 {
+        self.runouts
+    }
+    /// E[HS], the average equity after the next street, which is the same as the current equity
+    pub fn expected_hand_strength(&self) -> f64 {
+        self.equity_sum / self.runouts as f64
+    }
+    /// E[HS²], which rewards hands whose equity varies a lot (draws) over hands with the same E[HS]
+    pub fn expected_squared_hand_strength(&self) -> f64 {
+        self.squared_equity_sum / self.runouts as f64
+    }
+}
+
+/// Calculates the equity of the hand against a random opponent on every runout of the next street
+/// (the flop, or a single card on the flop and turn), with the given settings.
+/// The dead cards can neither come on the board nor be held by the opponent.
+/// Runouts that only differ by a permutation of the colors are only calculated once.
+/// Returns an error on duplicate cards, if the board has more than 4 cards, or if too few cards are left to deal
+pub fn equity_histogram(
+    hand: [Card; 2],
+    board: &[Card],
+    dead: &[Card],
+    bins: NonZeroUsize,
+    settings: Settings,
+) -> Result<EquityHistogram, Error> {
+    if board.len() > 4 {
+        return Err(Error::WrongCardCount {
+            min: 0,
+            max: 4,
+            found: board.len(),
+        });
+    }
+    let present_cards = CardSet::try_from_cards(
+        hand.into_iter()
+            .chain(board.iter().copied())
+            .chain(dead.iter().copied()),
+    )?;
+    let symmetries = isomorphism::symmetries(&[
+        CardSet::from_iter(hand),
+        CardSet::from_iter(board.iter().copied()),
+        CardSet::from_iter(dead.iter().copied()),
+    ]);
+
+    let remaining_deck = create_deck_without_present_cards(present_cards);
+    check_deck_size(remaining_deck.len(), 5 - board.len() + 2)?;
+    let next_street_size = if board.len() < 3 { 3 - board.len() } else { 1 };
+
+    let mut histogram = EquityHistogram::new(bins);
+    let mut next_board = board.to_vec();
+    for indices in Combinations::new(remaining_deck.len(), next_street_size) {
+        let runout: Vec<Card> = indices.iter().map(|&index| remaining_deck[index]).collect();
+        let Some(weight) = isomorphism::orbit_size(runout.iter().copied().collect(), &symmetries)
+        else {
+            continue;
+        };
+
+        next_board.truncate(board.len());
+        next_board.extend(runout);
+        let equity = calculate_with(hand, &next_board, dead, settings)?.equity();
+        histogram.record(equity, weight);
+    }
+
+    Ok(histogram)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Error, Hand, Settings, calculate, calculate_distribution, calculate_with,
+        distribution::equity_histogram,
+        io::parse_cards,
+        tests::{dead_leaving, hand},
+    };
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn categories() {
+        let board = parse_cards("AhKhQhJhTh").unwrap();
+        let distribution =
+            calculate_distribution(hand("2c3d"), &board, &[], Settings::default()).unwrap();
+        assert_eq!(distribution.hero(Hand::RoyalFlush), 990);
+        assert_eq!(distribution.opponent_frequency(Hand::RoyalFlush), 1.);
+
+        let board = parse_cards("Kh7c2d9s").unwrap();
+        let distribution =
+            calculate_distribution(hand("AsAd"), &board, &[], Settings::default()).unwrap();
+        let total = distribution.results().total();
+        assert_eq!(
+            Hand::ALL
+                .map(|hand| distribution.hero(hand))
+                .iter()
+                .sum::<u64>(),
+            total
+        );
+        assert_eq!(
+            Hand::ALL
+                .map(|hand| distribution.opponent(hand))
+                .iter()
+                .sum::<u64>(),
+            total
+        );
+        // Aces can only improve to two pair or trips on the river
+        for hand in Hand::ALL {
+            let possible = matches!(hand, Hand::Pair | Hand::TwoPair | Hand::ThreeOfAKind);
+            assert_eq!(distribution.hero(hand) > 0, possible, "{hand:?}");
+        }
+    }
+
+    #[test]
+    fn histogram() {
+        let board = parse_cards("Kh7h2d").unwrap();
+        let bins = NonZeroUsize::new(10).unwrap();
+
+        // A flush draw either hits or misses
+        let histogram =
+            equity_histogram(hand("AhQh"), &board, &[], bins, Settings::default()).unwrap();
+        assert_eq!(histogram.runouts(), 47);
+        assert_eq!(histogram.bins().iter().sum::<u64>(), 47);
+
+        let equity = calculate(hand("AhQh"), &board).unwrap().equity();
+        assert!((histogram.expected_hand_strength() - equity).abs() < 1e-12);
+        assert!(histogram.expected_squared_hand_strength() > equity * equity);
+
+        // Two dead hearts leave 7 flush cards out of 45
+        let dead = parse_cards("3h4h").unwrap();
+        let histogram =
+            equity_histogram(hand("AhQh"), &board, &dead, bins, Settings::default()).unwrap();
+        assert_eq!(histogram.runouts(), 45);
+        let equity = calculate_with(hand("AhQh"), &board, &dead, Settings::default())
+            .unwrap()
+            .equity();
+        assert!((histogram.expected_hand_strength() - equity).abs() < 1e-12);
+
+        // Only the turn, the river and one opponent card are left
+        let dead = dead_leaving(&[hand("AhQh").as_slice(), &board].concat(), 3);
+        assert_eq!(
+            equity_histogram(hand("AhQh"), &board, &dead, bins, Settings::default())
+                .map(|histogram| histogram.runouts()),
+            Err(Error::NotEnoughCards { needed: 4, left: 3 })
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{Card, CardValue, monte_carlo::MIN_SAMPLES};
+use std::{fmt, io};
+
+/// Everything that can go wrong when parsing input or calculating results
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Error {
+    /// There was no card to parse
+    Empty,
+    /// The text that should have been a value
+    InvalidValue(String),
+    /// The text that should have been a color
+    InvalidColor(String),
+    /// A value without a color, like the "K" in "AsK"
+    MissingColor(CardValue),
+    /// More than one card where a single card was expected
+    TrailingCharacters(String),
+    /// A card index outside of 0..52
+    InvalidCardIndex(u8),
+    /// The same card was given more than once
+    DuplicateCard(Card),
+    WrongCardCount {
+        min: usize,
+        max: usize,
+        found: usize,
+    },
+    /// The number of opponents, which has to be between 1 and `max`
+    /// (`MAX_PLAYERS - 1` at the table, `MAX_OPPONENTS` for the preflop tables)
+    InvalidOpponentCount { max: usize, found: usize },
+    /// The text that isnt valid range notation
+    InvalidRange(String),
+    /// A Monte Carlo estimate needs at least `MIN_SAMPLES` samples
+    InvalidSampleCount(u64),
+    /// Every combo of a range conflicts with the known cards
+    EmptyRange,
+    /// The dead cards leave too few cards in the deck for the board and the hands that are dealt from it
+    NotEnoughCards { needed: usize, left: usize },
+    /// A preflop table file with the wrong format or size
+    InvalidPreflopTable,
+    /// Reading the input failed
+    Io(io::ErrorKind),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Expected a card"),
+            Self::InvalidValue(value) => write!(f, "Invalid card value \"{value}\""),
+            Self::InvalidColor(color) => write!(f, "Invalid card color \"{color}\""),
+            Self::MissingColor(value) => write!(f, "Missing the color after \"{value}\""),
+            Self::TrailingCharacters(rest) => {
+                write!(f, "Unexpected \"{rest}\" after the card")
+            }
+            Self::InvalidCardIndex(index) => write!(f, "Invalid card index {index}"),
+            Self::DuplicateCard(card) => write!(f, "{card} was given more than once"),
+            Self::WrongCardCount { min, max, found } if min == max => {
+                write!(f, "Expected {min} cards, found {found}")
+            }
+            Self::WrongCardCount { min, max, found } => {
+                write!(f, "Expected between {min} and {max} cards, found {found}")
+            }
+            Self::InvalidOpponentCount { max, found } => {
+                write!(f, "Expected between 1 and {max} opponents, found {found}")
+            }
+            Self::InvalidSampleCount(samples) => write!(
+                f,
+                "Expected at least {MIN_SAMPLES} samples, found {samples}"
+            ),
+            Self::InvalidRange(range) => write!(f, "Invalid range \"{range}\""),
+            Self::EmptyRange => write!(
+                f,
+                "The range has no combos left after removing the known cards"
+            ),
+            Self::NotEnoughCards { needed, left } => write!(
+                f,
+                "Expected at least {needed} cards left in the deck, found {left}"
+            ),
+            Self::InvalidPreflopTable => write!(f, "Invalid preflop table file"),
+            Self::Io(kind) => write!(f, "Failed to read input: {kind}"),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error.kind())
+    }
+}
@@ -1,16 +1,19 @@
-use crate::{Card, Hand};
+use crate::{Card, Hand, HandRank};
 use std::marker::PhantomData;
 
 #[derive(Clone, Copy)]
-struct SingleColored;
+pub(crate) struct SingleColored;
 #[derive(Clone, Copy)]
-struct MultiColored;
+pub(crate) struct MultiColored;
+
+/// Mask of the wheel (A-2-3-4-5), the only straight that isnt a contiguous run of bits
+const WHEEL_MASK: u16 = 0b0001000000001111;
 
 #[derive(Clone, Copy)]
 /// A bitmap with one bit per `CardValue`
-struct ValueBitmap<State> {
+pub(crate) struct ValueBitmap<State> {
     // TODO: Maybe add a niche here
-    inner: u16,
+    pub(crate) inner: u16,
     _state: PhantomData<State>,
 }
 impl ValueBitmap<SingleColored> {
@@ -19,8 +22,9 @@ impl ValueBitmap<SingleColored> {
     }
 }
 impl<State> ValueBitmap<State> {
-    /// Returns the iteration the straight was found on, if any
-    fn is_straight(&self) -> Option<u8> {
+    /// Returns the iteration the straight was found on, if any.
+    /// The wheel is the weakest straight, so it is found on the last iteration (9)
+    pub(crate) fn is_straight(&self) -> Option<u8> {
         let straight_mask = 0b0001111100000000;
         for shift in 0..9 {
             let mask = straight_mask >> shift;
@@ -28,35 +32,70 @@ impl<State> ValueBitmap<State> {
                 return Some(shift);
             }
         }
+        if self.inner & WHEEL_MASK == WHEEL_MASK {
+            return Some(9);
+        }
         None
     }
-    fn new(inner: u16) -> Self {
+    pub(crate) fn new(inner: u16) -> Self {
         Self {
             inner,
             _state: PhantomData,
         }
     }
+    /// The highest value in the bitmap. Must not be empty
+    pub(crate) fn highest(&self) -> u8 {
+        15 - self.inner.leading_zeros() as u8
+    }
+    /// The values in the bitmap, from highest to lowest
+    fn values_descending(self) -> impl Iterator<Item = u8> {
+        let inner = self.inner;
+        (0..13).rev().filter(move |value| inner & 1 << value != 0)
+    }
+    fn without(&self, values: u16) -> Self {
+        Self::new(self.inner & !values)
+    }
 }
 
 /// One `ValueBitmap` per color
-struct ColorValueBitmaps([ValueBitmap<SingleColored>; 4]);
+pub(crate) struct ColorValueBitmaps(pub(crate) [ValueBitmap<SingleColored>; 4]);
 impl From<[Card; 7]> for ColorValueBitmaps {
     fn from(cards: [Card; 7]) -> Self {
         let mut color_value_bitmaps = ColorValueBitmaps([ValueBitmap::new(0); 4]);
 
         for card in cards {
-            color_value_bitmaps.0[card.color as usize].inner |= 1 << card.value as u8;
+            color_value_bitmaps.0[card.color().index() as usize].inner |= 1 << card.value().index();
         }
 
         color_value_bitmaps
     }
 }
+impl From<[u16; 4]> for ColorValueBitmaps {
+    fn from(color_bitmaps: [u16; 4]) -> Self {
+        ColorValueBitmaps(color_bitmaps.map(ValueBitmap::new))
+    }
+}
 impl ColorValueBitmaps {
     fn get_flush(&self) -> Option<ValueBitmap<SingleColored>> {
         self.0
             .into_iter()
             .find(|value_bitmap| value_bitmap.is_flush())
     }
+    /// All values present in any color
+    pub(crate) fn all_colors(&self) -> ValueBitmap<MultiColored> {
+        let [a, b, c, d] = self.0.map(|value_bitmap| value_bitmap.inner);
+        ValueBitmap::new(a | b | c | d)
+    }
+    /// All values present in at least two colors
+    pub(crate) fn at_least_pairs(&self) -> ValueBitmap<MultiColored> {
+        let [a, b, c, d] = self.0.map(|value_bitmap| value_bitmap.inner);
+        ValueBitmap::new((a & b) | (a & c) | (a & d) | (b & c) | (b & d) | (c & d))
+    }
+    /// All values present in at least three colors
+    fn at_least_trips(&self) -> ValueBitmap<MultiColored> {
+        let [a, b, c, d] = self.0.map(|value_bitmap| value_bitmap.inner);
+        ValueBitmap::new((a & b & c) | (a & b & d) | (a & c & d) | (b & c & d))
+    }
     fn is_four_of_a_kind(&self) -> bool {
         // TODO: See if manually initialising with first.inner is faster
         self.0
@@ -68,10 +107,19 @@ impl ColorValueBitmaps {
 }
 
 pub fn highest_hand(cards: [Card; 7]) -> Hand {
-    let color_value_bitmaps = ColorValueBitmaps::from(cards);
+    classify_color_value_bitmaps(ColorValueBitmaps::from(cards))
+}
+
+/// `highest_hand` of a hand given as one value bitmap per color, which may have up to seven cards
+pub(crate) fn highest_hand_from_bitmaps(color_bitmaps: [u16; 4]) -> Hand {
+    classify_color_value_bitmaps(ColorValueBitmaps::from(color_bitmaps))
+}
+
+fn classify_color_value_bitmaps(color_value_bitmaps: ColorValueBitmaps) -> Hand {
+    let flush = color_value_bitmaps.get_flush();
 
     // Check for Royal & Straight flushes
-    if let Some(flush) = color_value_bitmaps.get_flush()
+    if let Some(flush) = flush
         && let Some(iter) = flush.is_straight()
     {
         return if iter == 0 {
@@ -85,5 +133,237 @@ pub fn highest_hand(cards: [Card; 7]) -> Hand {
         return Hand::FourOfAKind;
     }
 
-    todo!()
+    let trips = color_value_bitmaps.at_least_trips();
+    let pairs = color_value_bitmaps.at_least_pairs();
+
+    // Either two trips, or trips and another pair
+    if trips.inner != 0 && pairs.inner.count_ones() >= 2 {
+        return Hand::FullHouse;
+    }
+
+    if flush.is_some() {
+        return Hand::Flush;
+    }
+
+    if color_value_bitmaps.all_colors().is_straight().is_some() {
+        return Hand::Straight;
+    }
+
+    if trips.inner != 0 {
+        return Hand::ThreeOfAKind;
+    }
+
+    match pairs.inner.count_ones() {
+        0 => Hand::HighCard,
+        1 => Hand::Pair,
+        _ => Hand::TwoPair,
+    }
+}
+
+/// Like `highest_hand`, but including the kickers, so that any two hands can be compared
+pub fn hand_rank(cards: [Card; 7]) -> HandRank {
+    rank_color_value_bitmaps(ColorValueBitmaps::from(cards))
+}
+
+/// `hand_rank` of a hand given as one value bitmap per color, which may have up to seven cards
+pub(crate) fn hand_rank_from_bitmaps(color_bitmaps: [u16; 4]) -> HandRank {
+    rank_color_value_bitmaps(ColorValueBitmaps::from(color_bitmaps))
+}
+
+fn rank_color_value_bitmaps(color_value_bitmaps: ColorValueBitmaps) -> HandRank {
+    let flush = color_value_bitmaps.get_flush();
+
+    if let Some(flush) = flush
+        && let Some(iter) = flush.is_straight()
+    {
+        // The shift is the distance of the highest card from the ace
+        // (which also works for the wheel, whose highest card is a five)
+        let hand = if iter == 0 {
+            Hand::RoyalFlush
+        } else {
+            Hand::StraightFlush
+        };
+        return HandRank::new(hand, [12 - iter]);
+    }
+
+    let all_colors = color_value_bitmaps.all_colors();
+
+    if color_value_bitmaps.is_four_of_a_kind() {
+        let quads = color_value_bitmaps
+            .0
+            .iter()
+            .fold(u16::MAX, |acc, value_bitmap| acc & value_bitmap.inner);
+        let quads = ValueBitmap::<MultiColored>::new(quads).highest();
+        // Hands with less than five cards can lack the kicker
+        let kicker = all_colors.without(1 << quads).values_descending().next();
+
+        return HandRank::new(Hand::FourOfAKind, [quads].into_iter().chain(kicker));
+    }
+
+    let trips = color_value_bitmaps.at_least_trips();
+    let pairs = color_value_bitmaps.at_least_pairs();
+
+    if trips.inner != 0 && pairs.inner.count_ones() >= 2 {
+        let trips = trips.highest();
+        // The second trips counts as the pair
+        let pair = pairs.without(1 << trips).highest();
+
+        return HandRank::new(Hand::FullHouse, [trips, pair]);
+    }
+
+    if let Some(flush) = flush {
+        return HandRank::new(Hand::Flush, flush.values_descending());
+    }
+
+    if let Some(iter) = all_colors.is_straight() {
+        return HandRank::new(Hand::Straight, [12 - iter]);
+    }
+
+    if trips.inner != 0 {
+        let trips = trips.highest();
+        let kickers = all_colors.without(1 << trips).values_descending().take(2);
+
+        return HandRank::new(Hand::ThreeOfAKind, [trips].into_iter().chain(kickers));
+    }
+
+    match pairs.inner.count_ones() {
+        0 => HandRank::new(Hand::HighCard, all_colors.values_descending()),
+        1 => {
+            let pair = pairs.highest();
+            let kickers = all_colors.without(pairs.inner).values_descending().take(3);
+
+            HandRank::new(Hand::Pair, [pair].into_iter().chain(kickers))
+        }
+        _ => {
+            // With three pairs, the lowest one can still play as the kicker
+            let mut pair_values = pairs.values_descending();
+            let high_pair = pair_values.next().unwrap();
+            let low_pair = pair_values.next().unwrap();
+            let kicker = all_colors
+                .without(1 << high_pair | 1 << low_pair)
+                .values_descending()
+                .next();
+
+            HandRank::new(
+                Hand::TwoPair,
+                [high_pair, low_pair].into_iter().chain(kicker),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Card, CardValue, Color, Hand,
+        card_set::CardSet,
+        combinations::Combinations,
+        highest_hand::{hand_rank, highest_hand},
+        tests::cards,
+    };
+    use std::collections::HashSet;
+
+    #[test]
+    fn kickers() {
+        // Pair of aces, king kicker beats pair of aces, queen kicker
+        let ace_king = cards("Ah Ad Kc 9s 7h 4d 2c");
+        let ace_queen = cards("Ah Ad Qc 9s 7h 4d 2c");
+        assert!(hand_rank(ace_king) > hand_rank(ace_queen));
+        assert_eq!(hand_rank(ace_king).hand(), Hand::Pair);
+
+        // Pairs of different values dont draw
+        let kings = cards("Kh Kd Qc 9s 7h 4d 2c");
+        assert!(hand_rank(ace_queen) > hand_rank(kings));
+
+        // Only the best five cards count
+        let sixth_card_differs = cards("Ah Ad Kc 9s 7h 4d 3c");
+        assert_eq!(hand_rank(ace_king), hand_rank(sixth_card_differs));
+    }
+
+    #[test]
+    fn incomplete_hands() {
+        let rank = |cards: &[Card]| CardSet::from_iter(cards.iter().copied()).hand_rank();
+
+        // Four cards can make quads or two pair without a kicker
+        let quads = rank(&cards::<4>("Ah Ad Ac As"));
+        assert_eq!(quads.hand(), Hand::FourOfAKind);
+        assert!(rank(&cards::<5>("Ah Ad Ac As 3h")) > quads);
+        assert!(quads > rank(&cards::<5>("Kh Kd Kc Ks Ah")));
+
+        let two_pair = rank(&cards::<4>("Ah Ad Kc Ks"));
+        assert_eq!(two_pair.hand(), Hand::TwoPair);
+        assert!(rank(&cards::<5>("Ah Ad Kc Ks 3h")) > two_pair);
+        assert!(two_pair > rank(&cards::<5>("Ah Ad Qc Qs Kh")));
+    }
+
+    #[test]
+    fn category_boundaries() {
+        // The wheel is the lowest straight
+        let wheel = cards("Ah 2d 3c 4s 5h 9d Kc");
+        let six_high = cards("2d 3c 4s 5h 6h 9d Kc");
+        assert_eq!(hand_rank(wheel).hand(), Hand::Straight);
+        assert!(hand_rank(six_high) > hand_rank(wheel));
+
+        // A steel wheel is still a straight flush
+        let steel_wheel = cards("Ah 2h 3h 4h 5h 9d Kc");
+        assert_eq!(hand_rank(steel_wheel).hand(), Hand::StraightFlush);
+
+        // Two trips make a full house with the higher ones on top
+        let two_trips = cards("9h 9d 9c 5h 5d 5c Kc");
+        let nines_full_of_twos = cards("9h 9d 9c 2h 2d 5c Kc");
+        assert_eq!(hand_rank(two_trips).hand(), Hand::FullHouse);
+        assert!(hand_rank(two_trips) > hand_rank(nines_full_of_twos));
+
+        // The lowest of three pairs can still be the kicker
+        let three_pairs = cards("9h 9d 5c 5h 4d 4c 2c");
+        let two_pairs_king = cards("9h 9d 5c 5h Kd 4c 2c");
+        assert_eq!(hand_rank(three_pairs).hand(), Hand::TwoPair);
+        assert!(hand_rank(two_pairs_king) > hand_rank(three_pairs));
+
+        let royal_flush = [
+            Card::new(CardValue::Ace, Color::Spades),
+            Card::new(CardValue::King, Color::Spades),
+            Card::new(CardValue::Queen, Color::Spades),
+            Card::new(CardValue::Jack, Color::Spades),
+            Card::new(CardValue::Ten, Color::Spades),
+            Card::new(CardValue::Two, Color::Hearts),
+            Card::new(CardValue::Three, Color::Hearts),
+        ];
+        assert_eq!(hand_rank(royal_flush).hand(), Hand::RoyalFlush);
+        assert!(hand_rank(royal_flush) > hand_rank(steel_wheel));
+    }
+
+    #[test]
+    #[ignore = "enumerates all 133,784,560 hands, run with --release"]
+    fn all_seven_card_hands() {
+        let deck: Vec<Card> = (0..52).filter_map(Card::from_index).collect();
+
+        // Indexed by the position of the variant in `Hand`
+        let mut counts = [0_u64; 10];
+        let mut distinct_ranks = HashSet::new();
+        for indices in Combinations::new(52, 7) {
+            let cards = std::array::from_fn(|position| deck[indices[position]]);
+            let hand = highest_hand(cards);
+            let rank = hand_rank(cards);
+            assert_eq!(rank.hand(), hand);
+            distinct_ranks.insert(rank);
+            counts[hand as usize] += 1;
+        }
+
+        // Number of equivalence classes of seven card hands
+        assert_eq!(distinct_ranks.len(), 4824);
+
+        assert_eq!(counts, [
+            23_294_460, // High card
+            58_627_800, // Pair
+            31_433_400, // Two pair
+            6_461_620,  // Three of a kind
+            6_180_020,  // Straight
+            4_047_644,  // Flush
+            3_473_184,  // Full house
+            224_848,    // Four of a kind
+            37_260,     // Straight flush
+            4_324,      // Royal flush
+        ]);
+    }
 }
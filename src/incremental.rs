@@ -0,0 +1,158 @@
+use crate::{
+    Card, HandRank,
+    card_set::CardSet,
+    combinations::{Combination, Combinations},
+};
+
+/// A hand that cards can be added to and removed from in O(1),
+/// by keeping the cards in a running `CardSet`.
+/// Only this bookkeeping is incremental, the rank is evaluated from all the cards
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IncrementalHand {
+    cards: CardSet,
+}
+impl IncrementalHand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds a card, which must not already be in the hand
+    pub fn add(&mut self, card: Card) {
+        let inserted = self.cards.insert(card);
+        debug_assert!(inserted, "{card:?} is already in the hand");
+    }
+    /// Removes a card, which must be in the hand
+    pub fn remove(&mut self, card: Card) {
+        let removed = self.cards.remove(card);
+        debug_assert!(removed, "{card:?} is not in the hand");
+    }
+    pub fn contains(&self, card: Card) -> bool {
+        self.cards.contains(card)
+    }
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+    pub fn cards(&self) -> CardSet {
+        self.cards
+    }
+    /// The rank of the hand, which may have at most seven cards.
+    /// Evaluates the whole hand on every call, the cost doesnt depend on the cards added or removed since the last one
+    pub fn hand_rank(&self) -> HandRank {
+        self.cards.hand_rank()
+    }
+}
+impl From<CardSet> for IncrementalHand {
+    fn from(cards: CardSet) -> Self {
+        Self { cards }
+    }
+}
+impl<const N: usize> From<[Card; N]> for IncrementalHand {
+    fn from(cards: [Card; N]) -> Self {
+        let mut hand = Self::new();
+        for card in cards {
+            hand.add(card);
+        }
+        hand
+    }
+}
+
+/// An iterator over all length r combinations of a deck, together with a base hand
+/// extended by the cards of the combination.
+/// Only the cards at the changed indices are swapped out on every step
+pub struct IncrementalCombinations<'a> {
+    deck: &'a [Card],
+    combinations: Combinations,
+    previous: Option<Combination>,
+    hand: IncrementalHand,
+}
+impl<'a> IncrementalCombinations<'a> {
+    pub fn new(deck: &'a [Card], r: usize, hand: IncrementalHand) -> Self {
+        Self {
+            deck,
+            combinations: Combinations::new(deck.len(), r),
+            previous: None,
+            hand,
+        }
+    }
+    /// Starts at the combination at the given position in lexicographic order
+    pub(crate) fn starting_at(
+        deck: &'a [Card],
+        r: usize,
+        position: usize,
+        hand: IncrementalHand,
+    ) -> Self {
+        Self {
+            combinations: Combinations::starting_at(deck.len(), r, position),
+            ..Self::new(deck, r, hand)
+        }
+    }
+}
+impl Iterator for IncrementalCombinations<'_> {
+    type Item = (Combination, IncrementalHand);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (indices, changed) = self.combinations.next_with_changed()?;
+
+        if let Some(previous) = self.previous {
+            for &index in &previous[changed..] {
+                self.hand.remove(self.deck[index]);
+            }
+        }
+        for &index in &indices[changed..] {
+            self.hand.add(self.deck[index]);
+        }
+        self.previous = Some(indices);
+
+        Some((indices, self.hand))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Card, evaluate,
+        incremental::{IncrementalCombinations, IncrementalHand},
+    };
+
+    fn deck<const N: usize>() -> [Card; N] {
+        let deck: Vec<Card> = (0..N as u8).filter_map(Card::from_index).collect();
+        deck.try_into().unwrap()
+    }
+
+    #[test]
+    fn add_remove() {
+        let [first, second] = deck();
+
+        let mut hand = IncrementalHand::from([first]);
+        hand.add(second);
+        assert!(hand.contains(first) && hand.contains(second));
+        assert_eq!(hand.len(), 2);
+
+        hand.remove(first);
+        assert!(!hand.contains(first));
+        assert_eq!(hand, IncrementalHand::from([second]));
+    }
+
+    #[test]
+    fn same_as_evaluate() {
+        let deck: [Card; 16] = deck();
+        let present = [deck[0], deck[5], deck[10]];
+        let remaining: [Card; 13] =
+            std::array::from_fn(|index| deck[[1, 2, 3, 4, 6, 7, 8, 9, 11, 12, 13, 14, 15][index]]);
+
+        for (indices, hand) in IncrementalCombinations::new(&remaining, 4, present.into()) {
+            let cards = [
+                present[0],
+                present[1],
+                present[2],
+                remaining[indices[0]],
+                remaining[indices[1]],
+                remaining[indices[2]],
+                remaining[indices[3]],
+            ];
+            assert_eq!(hand, IncrementalHand::from(cards));
+            assert_eq!(hand.hand_rank(), evaluate(cards));
+        }
+    }
+}
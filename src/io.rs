@@ -1,73 +1,283 @@
-use crate::{Card, CardValue, Color};
-use std::{io, str::FromStr};
+use crate::{Card, CardValue, Color, Error, Hand};
+use std::{fmt, str::FromStr};
+
+impl CardValue {
+    /// The character in standard notation, "T" for ten
+    pub const fn to_char(self) -> char {
+        match self {
+            Self::Two => '2',
+            Self::Three => '3',
+            Self::Four => '4',
+            Self::Five => '5',
+            Self::Six => '6',
+            Self::Seven => '7',
+            Self::Eight => '8',
+            Self::Nine => '9',
+            Self::Ten => 'T',
+            Self::Jack => 'J',
+            Self::Queen => 'Q',
+            Self::King => 'K',
+            Self::Ace => 'A',
+        }
+    }
+    /// Case-insensitive
+    pub const fn from_char(char: char) -> Option<Self> {
+        match char.to_ascii_uppercase() {
+            '2' => Some(Self::Two),
+            '3' => Some(Self::Three),
+            '4' => Some(Self::Four),
+            '5' => Some(Self::Five),
+            '6' => Some(Self::Six),
+            '7' => Some(Self::Seven),
+            '8' => Some(Self::Eight),
+            '9' => Some(Self::Nine),
+            'T' => Some(Self::Ten),
+            'J' => Some(Self::Jack),
+            'Q' => Some(Self::Queen),
+            'K' => Some(Self::King),
+            'A' => Some(Self::Ace),
+            _ => None,
+        }
+    }
+}
+impl Color {
+    /// The lowercase first letter of the color
+    pub const fn to_char(self) -> char {
+        match self {
+            Self::Hearts => 'h',
+            Self::Diamonds => 'd',
+            Self::Clubs => 'c',
+            Self::Spades => 's',
+        }
+    }
+    /// Case-insensitive, also accepts the unicode suits
+    pub const fn from_char(char: char) -> Option<Self> {
+        match char.to_ascii_lowercase() {
+            'h' | '♥' | '♡' => Some(Self::Hearts),
+            'd' | '♦' | '♢' => Some(Self::Diamonds),
+            'c' | '♣' | '♧' => Some(Self::Clubs),
+            's' | '♠' | '♤' => Some(Self::Spades),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CardValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+/// Standard notation, like "As" or "Td"
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value(), self.color())
+    }
+}
+
+/// The name of the category, like "Two pair"
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::HighCard => "High card",
+            Self::Pair => "Pair",
+            Self::TwoPair => "Two pair",
+            Self::ThreeOfAKind => "Three of a kind",
+            Self::Straight => "Straight",
+            Self::Flush => "Flush",
+            Self::FullHouse => "Full house",
+            Self::FourOfAKind => "Four of a kind",
+            Self::StraightFlush => "Straight flush",
+            Self::RoyalFlush => "Royal flush",
+        };
+        // Pad, so the names can be aligned in tables
+        f.pad(name)
+    }
+}
+
+/// Parses one card in standard notation off the start of the string, returns the rest
+fn parse_card_prefix(s: &str) -> Result<(Card, &str), Error> {
+    let mut chars = s.chars();
+    let value = chars.next().ok_or(Error::Empty)?;
+
+    // Allow "10" for ten
+    let (value, rest) = if let Some(rest) = s.strip_prefix("10") {
+        (CardValue::Ten, rest)
+    } else {
+        let value =
+            CardValue::from_char(value).ok_or_else(|| Error::InvalidValue(value.to_string()))?;
+        (value, chars.as_str())
+    };
+
+    let mut chars = rest.chars();
+    let color = chars.next().ok_or(Error::MissingColor(value))?;
+    let color = Color::from_char(color).ok_or_else(|| Error::InvalidColor(color.to_string()))?;
+
+    Ok((Card::new(value, color), chars.as_str()))
+}
+
+/// Parses cards in standard notation, either concatenated ("AsKd", "Ah7c2d")
+/// or separated by whitespace ("As Kd").
+/// Whitespace separated cards can also be in the verbose `Value,Color` format
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, Error> {
+    let mut cards = Vec::new();
+
+    for mut word in s.split_whitespace() {
+        if word.contains(',') {
+            cards.push(word.parse()?);
+            continue;
+        }
+
+        while !word.is_empty() {
+            let (card, rest) = parse_card_prefix(word)?;
+            cards.push(card);
+            word = rest;
+        }
+    }
+
+    Ok(cards)
+}
 
 impl FromStr for Card {
-    type Err = &'static str;
+    type Err = Error;
+    /// Either standard notation ("As") or the verbose `Value,Color` format ("Ace,Spades", "A,s")
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // Split the string into Color and Value
-        let card_parts: Vec<&str> = s.split(',').collect();
-        if card_parts.len() != 2 {
-            return Err("Invalid card format");
+        if let Some((value, color)) = s.split_once(',') {
+            return Ok(Self::new(value.parse()?, color.parse()?));
         }
 
-        let value = card_parts[0].parse()?;
-        let color = card_parts[1].parse()?;
-
-        Ok(Self::new(value, color))
+        match parse_card_prefix(s)? {
+            (card, "") => Ok(card),
+            (_, rest) => Err(Error::TrailingCharacters(rest.to_string())),
+        }
     }
 }
 
+/// The name ("Ace") or the standard notation ("A", "10").
+/// Numeric indices are only converted by `TryFrom<u8>`, so "14" isnt a value
 impl FromStr for CardValue {
-    type Err = &'static str;
+    type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "2" => Ok(Self::Two),
-            "3" => Ok(Self::Three),
-            "4" => Ok(Self::Four),
-            "5" => Ok(Self::Five),
-            "6" => Ok(Self::Six),
-            "7" => Ok(Self::Seven),
-            "8" => Ok(Self::Eight),
-            "9" => Ok(Self::Nine),
-            "10" => Ok(Self::Ten),
-            "Jack" | "11" | "j" | "J" => Ok(Self::Jack),
-            "Queen" | "12" | "q" | "Q" => Ok(Self::Queen),
-            "King" | "13" | "k" | "K" => Ok(Self::King),
-            "Ace" | "1" | "14" | "a" | "A" => Ok(Self::Ace),
-            // maybe add error handling
-            _otherwise => Err("Failed to parse value"),
+            "Two" => Ok(Self::Two),
+            "Three" => Ok(Self::Three),
+            "Four" => Ok(Self::Four),
+            "Five" => Ok(Self::Five),
+            "Six" => Ok(Self::Six),
+            "Seven" => Ok(Self::Seven),
+            "Eight" => Ok(Self::Eight),
+            "Nine" => Ok(Self::Nine),
+            "Ten" | "10" => Ok(Self::Ten),
+            "Jack" => Ok(Self::Jack),
+            "Queen" => Ok(Self::Queen),
+            "King" => Ok(Self::King),
+            "Ace" => Ok(Self::Ace),
+            otherwise => {
+                let mut chars = otherwise.chars();
+                match (chars.next().and_then(CardValue::from_char), chars.next()) {
+                    (Some(value), None) => Ok(value),
+                    _ => Err(Error::InvalidValue(otherwise.to_string())),
+                }
+            }
         }
     }
 }
 
+/// The name ("Spades") or the standard notation ("s", "♠")
 impl FromStr for Color {
-    type Err = &'static str;
+    type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "Hearts" | "1" => Ok(Self::Hearts),
-            "Diamonds" | "2" => Ok(Self::Diamonds),
-            "Clubs" | "3" => Ok(Self::Clubs),
-            "Spades" | "4" => Ok(Self::Spades),
-            // maybe add error handling
-            _otherwise => Err("Failed to parse color"),
+            "Hearts" => Ok(Self::Hearts),
+            "Diamonds" => Ok(Self::Diamonds),
+            "Clubs" => Ok(Self::Clubs),
+            "Spades" => Ok(Self::Spades),
+            otherwise => {
+                let mut chars = otherwise.chars();
+                match (chars.next().and_then(Color::from_char), chars.next()) {
+                    (Some(color), None) => Ok(color),
+                    _ => Err(Error::InvalidColor(otherwise.to_string())),
+                }
+            }
         }
     }
 }
 
-pub fn get_cards() -> Result<Vec<Card>, &'static str> {
-    // get input for cards
-    println!(
-        "Please input your handcards and the cards in the middle in the following format, separated by whitespace: Value,Color"
-    );
-    let mut hand_buffer = String::new();
-    let _ = io::stdin().read_line(&mut hand_buffer);
+#[cfg(test)]
+mod tests {
+    use crate::{Card, CardValue, Color, Error, io::parse_cards};
+
+    #[test]
+    fn standard_notation() {
+        let ace_of_spades = Card::new(CardValue::Ace, Color::Spades);
+        assert_eq!("As".parse(), Ok(ace_of_spades));
+        assert_eq!("aS".parse(), Ok(ace_of_spades));
+        assert_eq!("A♠".parse(), Ok(ace_of_spades));
+        assert_eq!("Ace,Spades".parse(), Ok(ace_of_spades));
+        assert_eq!("A,s".parse(), Ok(ace_of_spades));
+
+        let ten_of_diamonds = Card::new(CardValue::Ten, Color::Diamonds);
+        assert_eq!("Td".parse(), Ok(ten_of_diamonds));
+        assert_eq!("10d".parse(), Ok(ten_of_diamonds));
+    }
 
-    // Separate the Cards
-    let cards_string: Vec<&str> = hand_buffer.split_whitespace().collect();
+    #[test]
+    fn display_round_trip() {
+        for card in (0..52).filter_map(Card::from_index) {
+            assert_eq!(card.to_string().parse(), Ok(card));
+        }
+        assert_eq!(Card::new(CardValue::Seven, Color::Hearts).to_string(), "7h");
+    }
 
-    if cards_string.len() < 2 || cards_string.len() > 7 {
-        return Err("Please input between 2 and 7 cards");
+    #[test]
+    fn concatenated() {
+        let cards = parse_cards("AsKd Ah7c2d").unwrap();
+        let expected: Vec<Card> = ["As", "Kd", "Ah", "7c", "2d"]
+            .into_iter()
+            .map(|card| card.parse().unwrap())
+            .collect();
+        assert_eq!(cards, expected);
+
+        assert_eq!(parse_cards("  "), Ok(Vec::new()));
     }
 
-    cards_string.iter().map(|&string| string.parse()).collect()
+    #[test]
+    fn errors() {
+        assert_eq!("".parse::<Card>(), Err(Error::Empty));
+        assert_eq!(
+            "Xs".parse::<Card>(),
+            Err(Error::InvalidValue("X".to_string()))
+        );
+        assert_eq!(
+            "Ax".parse::<Card>(),
+            Err(Error::InvalidColor("x".to_string()))
+        );
+        assert_eq!(
+            "AsK".parse::<Card>(),
+            Err(Error::TrailingCharacters("K".to_string()))
+        );
+        assert_eq!(
+            parse_cards("AsK"),
+            Err(Error::MissingColor(CardValue::King))
+        );
+        assert_eq!(
+            "Ace,Hats".parse::<Card>(),
+            Err(Error::InvalidColor("Hats".to_string()))
+        );
+        // Numeric indices only convert through TryFrom, where they are zero based
+        assert_eq!(
+            "14,4".parse::<Card>(),
+            Err(Error::InvalidValue("14".to_string()))
+        );
+        assert_eq!("2".parse(), Ok(CardValue::Two));
+        assert_eq!(
+            "1".parse::<Color>(),
+            Err(Error::InvalidColor("1".to_string()))
+        );
+    }
 }
@@ -0,0 +1,191 @@
+use crate::{Card, Color, card_set::CardSet};
+
+/// A permutation of the four colors, maps the color with index i to the color with index `self.0[i]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColorPermutation([u8; 4]);
+impl ColorPermutation {
+    pub const IDENTITY: Self = Self([0, 1, 2, 3]);
+
+    /// All 24 permutations in lexicographic order, so starting with the identity
+    pub fn all() -> impl Iterator<Item = Self> {
+        // Every u8 is one assignment of four colors, with two bits each
+        (0..=u8::MAX)
+            .map(|n| Self([n / 64, n / 16 % 4, n / 4 % 4, n % 4]))
+            .filter(|permutation| {
+                permutation
+                    .0
+                    .iter()
+                    .fold(0_u8, |seen, &color| seen | 1 << color)
+                    == 0b1111
+            })
+    }
+    pub const fn color(self, color: Color) -> Color {
+        Color::ALL[self.0[color.index() as usize] as usize]
+    }
+    pub const fn card(self, card: Card) -> Card {
+        Card::new(card.value(), self.color(card.color()))
+    }
+    pub const fn cards(self, cards: CardSet) -> CardSet {
+        let bitmaps = cards.color_bitmaps();
+        let mut permuted = [0; 4];
+
+        let mut color = 0;
+        while color < 4 {
+            permuted[self.0[color] as usize] = bitmaps[color];
+            color += 1;
+        }
+        CardSet::from_color_bitmaps(permuted)
+    }
+    /// The permutation that maps every color back
+    pub const fn inverse(self) -> Self {
+        let mut inverse = [0; 4];
+
+        let mut color = 0;
+        while color < 4 {
+            inverse[self.0[color] as usize] = color as u8;
+            color += 1;
+        }
+        Self(inverse)
+    }
+}
+
+/// A hand and board mapped to the canonical form of all their suit isomorphic configurations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Canonical {
+    pub hand: CardSet,
+    pub board: CardSet,
+    /// Maps the original cards to the canonical ones, its inverse maps them back
+    pub permutation: ColorPermutation,
+}
+
+/// Maps the hand and board to their canonical form,
+/// which is the same for all configurations that only differ by a permutation of the colors.
+/// The canonical form has the largest hand bitmaps in the first colors, and then the largest board bitmaps
+pub fn canonicalize(hand: [Card; 2], board: &[Card]) -> Canonical {
+    let (hand, board) = (
+        CardSet::from_iter(hand),
+        CardSet::from_iter(board.iter().copied()),
+    );
+
+    ColorPermutation::all()
+        .map(|permutation| Canonical {
+            hand: permutation.cards(hand),
+            board: permutation.cards(board),
+            permutation,
+        })
+        .max_by_key(|canonical| {
+            (
+                canonical.hand.color_bitmaps(),
+                canonical.board.color_bitmaps(),
+            )
+        })
+        .expect("There is at least the identity")
+}
+
+/// The permutations that map each of the card groups to itself, including the identity.
+/// They form a group, so they can be used with `orbit_size`
+pub fn symmetries(groups: &[CardSet]) -> Vec<ColorPermutation> {
+    ColorPermutation::all()
+        .filter(|permutation| {
+            groups
+                .iter()
+                .all(|&cards| permutation.cards(cards) == cards)
+        })
+        .collect()
+}
+
+/// The number of distinct configurations the cards are mapped to by the symmetries,
+/// or None if the cards arent the canonical (largest) one among them.
+/// Enumerating only the canonical configurations weighted by this gives the same counts as enumerating all of them
+pub fn orbit_size(cards: CardSet, symmetries: &[ColorPermutation]) -> Option<u64> {
+    let bitmaps = cards.color_bitmaps();
+    let mut fixed = 0;
+    for permutation in symmetries {
+        let image = permutation.cards(cards).color_bitmaps();
+        if image > bitmaps {
+            return None;
+        }
+        if image == bitmaps {
+            fixed += 1;
+        }
+    }
+    Some((symmetries.len() / fixed) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Card,
+        card_set::CardSet,
+        combinations::Combinations,
+        io::parse_cards,
+        isomorphism::{ColorPermutation, canonicalize, orbit_size, symmetries},
+    };
+
+    fn card(card: &str) -> Card {
+        card.parse().unwrap()
+    }
+    fn cards(cards: &str) -> CardSet {
+        parse_cards(cards).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn permutations() {
+        assert_eq!(ColorPermutation::all().count(), 24);
+        assert_eq!(
+            ColorPermutation::all().next(),
+            Some(ColorPermutation::IDENTITY)
+        );
+
+        let hand = cards("AsKd7h");
+        for permutation in ColorPermutation::all() {
+            let permuted = permutation.cards(hand);
+            assert_eq!(permutation.inverse().cards(permuted), hand);
+            assert_eq!(
+                permuted,
+                hand.iter().map(|card| permutation.card(card)).collect()
+            );
+        }
+    }
+
+    #[test]
+    fn canonical() {
+        let canonical = canonicalize([card("As"), card("Ks")], &parse_cards("Qs7d2c").unwrap());
+        assert_eq!(canonical.hand, cards("AhKh"));
+        assert_eq!(canonical.board, cards("Qh7d2c"));
+        assert_eq!(
+            canonical.permutation.inverse().cards(canonical.board),
+            cards("Qs7d2c")
+        );
+
+        let isomorphic = canonicalize([card("Ac"), card("Kc")], &parse_cards("Qc7s2h").unwrap());
+        assert_eq!(
+            (isomorphic.hand, isomorphic.board),
+            (canonical.hand, canonical.board)
+        );
+        let different = canonicalize([card("Ac"), card("Kc")], &parse_cards("Qs7c2h").unwrap());
+        assert_ne!(
+            (different.hand, different.board),
+            (canonical.hand, canonical.board)
+        );
+    }
+
+    #[test]
+    fn orbit_sizes() {
+        let present = cards("AsKd");
+        let symmetries = symmetries(&[present]);
+        assert_eq!(symmetries.len(), 2);
+
+        // Every configuration is counted exactly once
+        let deck: Vec<_> = (!present).iter().collect();
+        let total: u64 = Combinations::new(deck.len(), 3)
+            .filter_map(|indices| {
+                orbit_size(
+                    indices.iter().map(|&index| deck[index]).collect(),
+                    &symmetries,
+                )
+            })
+            .sum();
+        assert_eq!(total, 19600);
+    }
+}
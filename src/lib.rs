@@ -1,33 +1,94 @@
-#![feature(generic_const_exprs)]
-#![feature(array_try_from_fn)]
-#![feature(maybe_uninit_uninit_array)]
-#![feature(maybe_uninit_array_assume_init)]
-#![feature(slice_as_array)]
-#![feature(let_chains)]
-
-use combinations::{CombinationMap, Combinations, num_combinations};
-use highest_hand::highest_hand;
-use std::{array, mem::MaybeUninit, ptr};
-
+use card_set::CardSet;
+use combinations::{Combination, CombinationMap, Combinations, num_combinations};
+use distribution::Distribution;
+pub use error::Error;
+use incremental::{IncrementalCombinations, IncrementalHand};
+use isomorphism::ColorPermutation;
+use std::{cmp::Ordering, fmt, num::NonZeroUsize, ops::AddAssign, thread};
+
+pub mod analysis;
+pub mod card_set;
 pub mod combinations;
+pub mod distribution;
+mod error;
 pub mod highest_hand;
+pub mod incremental;
 pub mod io;
+pub mod isomorphism;
+#[cfg(feature = "lookup-table")]
+pub mod lookup_table;
+pub mod monte_carlo;
+pub mod multiway;
+pub mod preflop;
+pub mod range;
+pub mod range_equity;
+pub mod report;
+
+/// The evaluator used by `calculate`.
+/// Uses `ColorValueBitmaps` by default, and precomputed tables with the `lookup-table` feature
+#[cfg(not(feature = "lookup-table"))]
+pub use highest_hand::hand_rank as evaluate;
+#[cfg(feature = "lookup-table")]
+pub use lookup_table::hand_rank as evaluate;
 
 const FULL_DECK_SIZE: usize = 52;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
-pub struct Card {
-    value: CardValue,
-    color: Color,
-}
+/// A card, stored as its index in the deck (0..52).
+/// The index is `color * 13 + value`, so the cards are ordered by color and then by value,
+/// the same way as in a `CardSet`
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Card(u8);
 impl Card {
-    pub fn new(value: CardValue, color: Color) -> Self {
-        Self { value, color }
+    pub const fn new(value: CardValue, color: Color) -> Self {
+        Self(color.index() * 13 + value.index())
+    }
+    pub const fn value(self) -> CardValue {
+        CardValue::ALL[(self.0 % 13) as usize]
+    }
+    pub const fn color(self) -> Color {
+        Color::ALL[(self.0 / 13) as usize]
+    }
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+    /// Returns None if the index isnt in 0..52
+    pub const fn from_index(index: u8) -> Option<Self> {
+        if (index as usize) < FULL_DECK_SIZE {
+            Some(Self(index))
+        } else {
+            None
+        }
+    }
+}
+impl Default for Card {
+    fn default() -> Self {
+        Self::new(CardValue::default(), Color::default())
+    }
+}
+impl fmt::Debug for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Card")
+            .field("value", &self.value())
+            .field("color", &self.color())
+            .finish()
+    }
+}
+impl From<Card> for u8 {
+    fn from(card: Card) -> Self {
+        card.index()
+    }
+}
+impl TryFrom<u8> for Card {
+    type Error = Error;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        Self::from_index(index).ok_or(Error::InvalidCardIndex(index))
     }
 }
 impl TryFrom<(u8, u8)> for Card {
-    type Error = &'static str;
+    type Error = Error;
 
+    /// From the indices of the value and the color
     fn try_from((value, color): (u8, u8)) -> Result<Self, Self::Error> {
         Ok(Self::new(value.try_into()?, color.try_into()?))
     }
@@ -50,45 +111,47 @@ pub enum CardValue {
     King,
     Ace,
 }
+impl CardValue {
+    /// All values, ordered by their index
+    pub const ALL: [Self; 13] = [
+        Self::Two,
+        Self::Three,
+        Self::Four,
+        Self::Five,
+        Self::Six,
+        Self::Seven,
+        Self::Eight,
+        Self::Nine,
+        Self::Ten,
+        Self::Jack,
+        Self::Queen,
+        Self::King,
+        Self::Ace,
+    ];
+
+    /// 0 for `Two` up to 12 for `Ace`
+    pub const fn index(self) -> u8 {
+        self as u8
+    }
+    /// Returns None if the index isnt in 0..13
+    pub const fn from_index(index: u8) -> Option<Self> {
+        if (index as usize) < Self::ALL.len() {
+            Some(Self::ALL[index as usize])
+        } else {
+            None
+        }
+    }
+}
 impl From<CardValue> for u8 {
     fn from(value: CardValue) -> Self {
-        match value {
-            CardValue::Two => 2,
-            CardValue::Three => 3,
-            CardValue::Four => 4,
-            CardValue::Five => 5,
-            CardValue::Six => 6,
-            CardValue::Seven => 7,
-            CardValue::Eight => 8,
-            CardValue::Nine => 9,
-            CardValue::Ten => 10,
-            CardValue::Jack => 11,
-            CardValue::Queen => 12,
-            CardValue::King => 13,
-            CardValue::Ace => 14,
-        }
+        value.index()
     }
 }
 impl TryFrom<u8> for CardValue {
-    type Error = &'static str;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            2 => Ok(CardValue::Two),
-            3 => Ok(CardValue::Three),
-            4 => Ok(CardValue::Four),
-            5 => Ok(CardValue::Five),
-            6 => Ok(CardValue::Six),
-            7 => Ok(CardValue::Seven),
-            8 => Ok(CardValue::Eight),
-            9 => Ok(CardValue::Nine),
-            10 => Ok(CardValue::Ten),
-            11 => Ok(CardValue::Jack),
-            12 => Ok(CardValue::Queen),
-            13 => Ok(CardValue::King),
-            14 => Ok(CardValue::Ace),
-            _ => Err("Invalid card value"),
-        }
+    type Error = Error;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        Self::from_index(index).ok_or_else(|| Error::InvalidValue(index.to_string()))
     }
 }
 
@@ -100,207 +163,647 @@ pub enum Color {
     Clubs,
     Spades,
 }
-impl From<Color> for u8 {
-    fn from(value: Color) -> Self {
-        match value {
-            Color::Hearts => 1,
-            Color::Diamonds => 2,
-            Color::Clubs => 3,
-            Color::Spades => 4,
+impl Color {
+    /// All colors, ordered by their index
+    pub const ALL: [Self; 4] = [Self::Hearts, Self::Diamonds, Self::Clubs, Self::Spades];
+
+    /// 0 for `Hearts` up to 3 for `Spades`
+    pub const fn index(self) -> u8 {
+        self as u8
+    }
+    /// Returns None if the index isnt in 0..4
+    pub const fn from_index(index: u8) -> Option<Self> {
+        if (index as usize) < Self::ALL.len() {
+            Some(Self::ALL[index as usize])
+        } else {
+            None
         }
     }
 }
+impl From<Color> for u8 {
+    fn from(color: Color) -> Self {
+        color.index()
+    }
+}
 impl TryFrom<u8> for Color {
-    type Error = &'static str;
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(Color::Hearts),
-            2 => Ok(Color::Diamonds),
-            3 => Ok(Color::Clubs),
-            4 => Ok(Color::Spades),
-            _ => Err("Invalid color"),
-        }
+    type Error = Error;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        Self::from_index(index).ok_or_else(|| Error::InvalidColor(index.to_string()))
     }
 }
 
+/// The category of a hand.
+/// Ordered by strength, so a stronger hand always compares as greater
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum Hand {
-    RoyalFlush,
-    StraightFlush,
-    FourOfAKind,
-    FullHouse,
-    Flush,
-    Straight,
-    ThreeOfAKind,
-    TwoPair,
-    Pair,
     HighCard,
+    Pair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+    RoyalFlush,
+}
+impl Hand {
+    /// All categories, from the weakest to the strongest
+    pub const ALL: [Self; 10] = [
+        Self::HighCard,
+        Self::Pair,
+        Self::TwoPair,
+        Self::ThreeOfAKind,
+        Self::Straight,
+        Self::Flush,
+        Self::FullHouse,
+        Self::FourOfAKind,
+        Self::StraightFlush,
+        Self::RoyalFlush,
+    ];
+
+    /// Strength of the category, from 0 (`HighCard`) to 9 (`RoyalFlush`)
+    const fn strength(self) -> u32 {
+        self as u32
+    }
+    const fn from_strength(strength: u32) -> Self {
+        match strength {
+            9 => Self::RoyalFlush,
+            8 => Self::StraightFlush,
+            7 => Self::FourOfAKind,
+            6 => Self::FullHouse,
+            5 => Self::Flush,
+            4 => Self::Straight,
+            3 => Self::ThreeOfAKind,
+            2 => Self::TwoPair,
+            1 => Self::Pair,
+            _ => Self::HighCard,
+        }
+    }
+}
+
+/// The full strength of a hand, including kickers.
+/// Stronger hands compare as greater, equal ranks split the pot.
+///
+/// Layout: the `Hand` strength in bits 20..24, followed by up to five
+/// `CardValue`s (4 bits each), most significant first
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct HandRank(u32);
+impl HandRank {
+    /// Lower than any real hand
+    pub const MIN: Self = Self(0);
+
+    /// Packs the hand category and its ranked kickers, most significant first
+    fn new(hand: Hand, kickers: impl IntoIterator<Item = u8>) -> Self {
+        let mut rank = hand.strength() << 20;
+        for (position, kicker) in kickers.into_iter().take(5).enumerate() {
+            rank |= (kicker as u32) << (16 - 4 * position);
+        }
+        Self(rank)
+    }
+    pub const fn hand(self) -> Hand {
+        Hand::from_strength(self.0 >> 20)
+    }
+}
+
+/// The outcome of a showdown from the players point of view, with the hand that won it.
+/// On a tie, both hands are equal
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Showdown {
+    Win(HandRank),
+    Tie(HandRank),
+    Lose(HandRank),
+}
+impl Showdown {
+    pub fn new(player_hand: HandRank, opponent_hand: HandRank) -> Self {
+        match player_hand.cmp(&opponent_hand) {
+            Ordering::Greater => Self::Win(player_hand),
+            Ordering::Equal => Self::Tie(player_hand),
+            Ordering::Less => Self::Lose(opponent_hand),
+        }
+    }
+    pub fn winning_hand(self) -> HandRank {
+        match self {
+            Self::Win(hand) | Self::Tie(hand) | Self::Lose(hand) => hand,
+        }
+    }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Results {
     wins: u64,
     draws: u64,
     losses: u64,
 }
+impl Results {
+    /// Records the showdown `weight` times
+    fn record(&mut self, showdown: Showdown, weight: u64) {
+        match showdown {
+            Showdown::Win(_) => self.wins += weight,
+            Showdown::Tie(_) => self.draws += weight,
+            Showdown::Lose(_) => self.losses += weight,
+        }
+    }
+    pub fn wins(&self) -> u64 {
+        self.wins
+    }
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+    pub fn losses(&self) -> u64 {
+        self.losses
+    }
+    pub fn total(&self) -> u64 {
+        self.wins + self.draws + self.losses
+    }
+    /// The share of the pot the player wins on average, with draws splitting it in half
+    pub fn equity(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.) / self.total() as f64
+    }
+}
+impl AddAssign for Results {
+    fn add_assign(&mut self, other: Self) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+}
 
-/// Create a full hand, from some present cards and a list of indices to the deck
-fn combine_cards_with_indices<const R: usize, const S: usize, const DECK_SIZE: usize>(
-    cards: [Card; R],
-    indices: [usize; S],
-    deck: &[Card; DECK_SIZE],
-) -> [Card; 7] {
-    debug_assert_eq!(S, 7 - R);
-
-    let mut combined_cards = MaybeUninit::uninit_array();
+/// How `calculate` enumerates the opponent hands and remaining boards
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Enumeration {
+    /// Every combination of cards
+    All,
+    /// Only one of every group of combinations that only differ by a permutation of the colors,
+    /// weighted by the size of the group. Gives the same results as `All` with up to 24x less work
+    #[default]
+    SuitIsomorphic,
+}
 
-    // SAFETY
-    // using copy_nonoverlapping is fine, because we copy into a locally defined array
-    unsafe {
-        ptr::copy_nonoverlapping(
-            cards.as_ptr() as *const MaybeUninit<Card>,
-            combined_cards.as_mut_ptr(),
-            R,
-        );
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    pub threads: NonZeroUsize,
+    pub enumeration: Enumeration,
+}
+impl Default for Settings {
+    /// All available cores, with suit isomorphic enumeration
+    fn default() -> Self {
+        Self {
+            threads: thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            enumeration: Enumeration::default(),
+        }
     }
+}
 
-    for (cards_index, deck_index) in indices.into_iter().enumerate() {
-        combined_cards[cards_index + R] = MaybeUninit::new(deck[deck_index]);
-    }
+/// Calculates the results of the hand against every possible opponent hand and remaining board,
+/// with the default `Settings`.
+/// Works on every street, the board can have 0 to 5 cards.
+/// Returns an error on duplicate cards, or if the board has too many cards
+pub fn calculate(hand: [Card; 2], board: &[Card]) -> Result<Results, Error> {
+    calculate_with(hand, board, &[], Settings::default())
+}
+
+/// Like `calculate`, but without the dead cards (like folded or exposed cards) in the deck,
+/// split across `settings.threads` threads and enumerated with `settings.enumeration`.
+/// The results are the same for all settings.
+/// Also returns an error if a dead card is in the hand or on the board, or if the dead cards leave too few cards to deal
+pub fn calculate_with(
+    hand: [Card; 2],
+    board: &[Card],
+    dead: &[Card],
+    settings: Settings,
+) -> Result<Results, Error> {
+    calculate_distribution(hand, board, dead, settings).map(|distribution| distribution.results())
+}
 
-    unsafe { MaybeUninit::array_assume_init(combined_cards) }
+/// Returns an error if the board has more than 5 cards
+pub fn check_board(board: &[Card]) -> Result<(), Error> {
+    if board.len() > 5 {
+        return Err(Error::WrongCardCount {
+            min: 0,
+            max: 5,
+            found: board.len(),
+        });
+    }
+    Ok(())
 }
 
-pub fn calculate<const NUM_CARDS: usize>(present_cards: [Card; NUM_CARDS]) -> Results
-where
-    [(); 7 - NUM_CARDS]:,
-    [(); FULL_DECK_SIZE - NUM_CARDS]:,
-    [(); 9 - NUM_CARDS]:,
-    // N = FULL_DECK_SIZE - NUM_CARDS, R = 7 - NUM_CARDS
-    [(); num_combinations(FULL_DECK_SIZE - NUM_CARDS, 7 - NUM_CARDS)]:,
-    [(); FULL_DECK_SIZE - NUM_CARDS - 1]:,
-    [(); 7 - NUM_CARDS - 1]:,
-    [(); NUM_CARDS - 2]:,
-{
-    let remaining_deck =
-        create_deck_without_present_cards(present_cards).expect("Failed to create remaining deck");
+/// Returns an error if the deck has less than the needed cards, which only dead cards can cause
+pub(crate) fn check_deck_size(left: usize, needed: usize) -> Result<(), Error> {
+    if left < needed {
+        return Err(Error::NotEnoughCards { needed, left });
+    }
+    Ok(())
+}
 
-    let mut player_hands: CombinationMap<{ FULL_DECK_SIZE - NUM_CARDS }, { 7 - NUM_CARDS }> =
-        const { CombinationMap::new() };
+/// Like `calculate_with`, but also counts the final `Hand` categories of both players
+pub fn calculate_distribution(
+    hand: [Card; 2],
+    board: &[Card],
+    dead: &[Card],
+    settings: Settings,
+) -> Result<Distribution, Error> {
+    check_board(board)?;
+
+    let present_cards = CardSet::try_from_cards(
+        hand.into_iter()
+            .chain(board.iter().copied())
+            .chain(dead.iter().copied()),
+    )?;
+    let present_pool = CardSet::from_iter(board.iter().copied());
+    let dead_cards = CardSet::from_iter(dead.iter().copied());
+
+    let remaining_deck = create_deck_without_present_cards(present_cards);
+    let remaining_pool_size = 5 - board.len();
+    check_deck_size(remaining_deck.len(), remaining_pool_size + 2)?;
+
+    let mut player_hands = CombinationMap::new(remaining_deck.len(), remaining_pool_size);
 
     // Fill hashmap with player hands
-    for (i, remaining_pool_indices) in
-        Combinations::<{ FULL_DECK_SIZE - NUM_CARDS }, { 7 - NUM_CARDS }>::new().enumerate()
+    for (i, (_, player_hand)) in IncrementalCombinations::new(
+        &remaining_deck,
+        remaining_pool_size,
+        (present_cards - dead_cards).into(),
+    )
+    .enumerate()
     {
-        let combined_cards =
-            combine_cards_with_indices(present_cards, remaining_pool_indices, &remaining_deck);
-
         // This iterator should be in lexicographic order, so directly indexing the array should be fine
-        player_hands.array[i] = highest_hand(combined_cards);
+        player_hands.array[i] = player_hand.hand_rank();
     }
 
-    let mut results = Results::default();
+    // Split the opponent combinations into one range of positions per thread
+    let num_opponent_combinations = num_combinations(remaining_deck.len(), remaining_pool_size + 2);
+    let chunk_size = num_opponent_combinations.div_ceil(settings.threads.get());
+
+    // Permutations of the colors that keep the players hand, the board and the dead cards the same
+    let symmetries = match settings.enumeration {
+        Enumeration::All => vec![ColorPermutation::IDENTITY],
+        Enumeration::SuitIsomorphic => {
+            isomorphism::symmetries(&[CardSet::from_iter(hand), present_pool, dead_cards])
+        }
+    };
+
+    let (remaining_deck, player_hands, symmetries) = (&remaining_deck, &player_hands, &symmetries);
+    Ok(thread::scope(|scope| {
+        let workers: Vec<_> = (0..num_opponent_combinations)
+            .step_by(chunk_size)
+            .map(|start| {
+                let len = chunk_size.min(num_opponent_combinations - start);
+                scope.spawn(move || {
+                    let opponent_hands = IncrementalCombinations::starting_at(
+                        remaining_deck,
+                        remaining_pool_size + 2,
+                        start,
+                        present_pool.into(),
+                    );
+                    count_showdowns(
+                        opponent_hands.take(len),
+                        player_hands,
+                        remaining_pool_size,
+                        symmetries,
+                    )
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("Worker thread panicked"))
+            .fold(
+                Distribution::default(),
+                |mut distribution, worker_distribution| {
+                    distribution += worker_distribution;
+                    distribution
+                },
+            )
+    }))
+}
+
+/// Counts the showdowns and categories of every given opponent combination
+/// (remaining pool and opponent hand) against the player hands.
+/// Only the combinations that are canonical under the symmetries are counted, weighted by their orbit size
+fn count_showdowns(
+    opponent_hands: impl Iterator<Item = (Combination, IncrementalHand)>,
+    player_hands: &CombinationMap,
+    remaining_pool_size: usize,
+    symmetries: &[ColorPermutation],
+) -> Distribution {
+    let mut distribution = Distribution::default();
 
-    // Calculate results
     // For all possible remaining cards
-    for card_indices in Combinations::<{ FULL_DECK_SIZE - NUM_CARDS }, { 9 - NUM_CARDS }>::new() {
-        // Get the present pool and convert it to an array
-        let present_pool: [Card; NUM_CARDS - 2] = *present_cards[2..]
-            .as_array()
-            .expect("present_cards.len() == NUM_CARDS -> present_cards[2..].len() == NUM_CARDS -2");
-
-        let combined_cards =
-            combine_cards_with_indices(present_pool, card_indices, &remaining_deck);
-        let highest_hand = highest_hand(combined_cards);
-
-        for remaining_pool in
-            Combinations::<{ FULL_DECK_SIZE - NUM_CARDS }, { 7 - NUM_CARDS }>::new()
-        {
-            let player_hand = player_hands[remaining_pool];
-
-            match highest_hand.cmp(&player_hand) {
-                std::cmp::Ordering::Less => results.losses += 1,
-                std::cmp::Ordering::Equal => results.draws += 1,
-                std::cmp::Ordering::Greater => results.wins += 1,
-            }
+    for (card_indices, opponent_hand) in opponent_hands {
+        let Some(weight) = isomorphism::orbit_size(opponent_hand.cards(), symmetries) else {
+            continue;
+        };
+        let opponent_hand = opponent_hand.hand_rank();
+
+        // The combination is split into a remaining pool and the opponents hand cards in every
+        // possible way, so each showdown is counted once and the pool never overlaps the hand
+        for pool_positions in Combinations::new(remaining_pool_size + 2, remaining_pool_size) {
+            let remaining_pool = pool_positions.map_indices(&card_indices);
+            let player_hand = player_hands[&remaining_pool];
+
+            distribution.record(player_hand, opponent_hand, weight);
         }
     }
 
-    results
+    distribution
 }
 
-// Necessary because caluclate doesnt work with seven cards
-pub fn calculate_7(present_cards: [Card; 7]) -> Results {
-    let remaining_deck =
-        create_deck_without_present_cards(present_cards).expect("Failed to create remaining deck");
+/// Creates a full poker deck, without the given present cards in it
+pub(crate) fn create_deck_without_present_cards(present_cards: CardSet) -> Vec<Card> {
+    present_cards.complement().iter().collect()
+}
 
-    let player_hand = highest_hand(present_cards);
+#[cfg(test)]
+mod tests {
+    use crate::{Card, CardValue, Color};
+
+    /// Parses whitespace separated cards like "As Ks"
+    pub(crate) fn cards<const N: usize>(cards: &str) -> [Card; N] {
+        let cards: Vec<Card> = cards
+            .split_whitespace()
+            .map(|card| card.parse().unwrap())
+            .collect();
+        cards.try_into().unwrap()
+    }
 
-    let mut results = Results::default();
+    /// Parses a hand in standard notation, like "AsKd"
+    pub(crate) fn hand(cards: &str) -> [Card; 2] {
+        crate::io::parse_cards(cards).unwrap().try_into().unwrap()
+    }
 
-    // Calculate results
-    // For all possible remaining cards
-    for card_indices in Combinations::<{ FULL_DECK_SIZE - 7 }, 2>::new() {
-        // Get the present pool and convert it to an array
-        let present_pool: [Card; 5] = *present_cards[2..]
-            .as_array()
-            .expect("present_cards.len() == NUM_CARDS -> present_cards[2..].len() == NUM_CARDS -2");
-
-        let combined_cards =
-            combine_cards_with_indices(present_pool, card_indices, &remaining_deck);
-        let highest_hand = highest_hand(combined_cards);
-
-        match highest_hand.cmp(&player_hand) {
-            std::cmp::Ordering::Less => results.losses += 1,
-            std::cmp::Ordering::Equal => results.draws += 1,
-            std::cmp::Ordering::Greater => results.wins += 1,
+    /// Every card except the known ones and `left` others, as dead cards that leave only `left` cards in the deck
+    pub(crate) fn dead_leaving(known: &[Card], left: usize) -> Vec<Card> {
+        (0..52)
+            .filter_map(Card::from_index)
+            .filter(|card| !known.contains(card))
+            .skip(left)
+            .collect()
+    }
+
+    pub(crate) mod showdown {
+        use super::cards;
+        use crate::{Card, Hand, HandRank, Showdown, highest_hand::hand_rank};
+        use std::cmp::Ordering;
+
+        /// Player hand cards, opponent hand cards, board, outcome for the player, winning category
+        #[rustfmt::skip]
+        const SHOWDOWNS: [(&str, &str, &str, Ordering, Hand); 10] = [
+            // Higher pair
+            ("Ah Ad", "Kh Kd", "2c 7s 9h Jd 4c", Ordering::Greater, Hand::Pair),
+            // Kicker
+            ("Ah Kd", "Ac Qh", "As 7s 2h 9d 4c", Ordering::Greater, Hand::Pair),
+            // Same straight
+            ("Ah Kd", "Ac Kh", "Qs Js Th 2d 3c", Ordering::Equal, Hand::Straight),
+            // Set beats two pair
+            ("2h 2d", "Ac Kh", "As 2s 7h 9d Kc", Ordering::Greater, Hand::ThreeOfAKind),
+            // Flush beats straight
+            ("Ah 3h", "9c Td", "Jh Qh 8h 2c 4s", Ordering::Greater, Hand::Flush),
+            // Full house beats straight
+            ("9c Td", "7h 7d", "Jh Qh 8h 7c 8s", Ordering::Less, Hand::FullHouse),
+            // The board plays
+            ("2h 3d", "4h 5d", "As Ks Qs Js Ts", Ordering::Equal, Hand::RoyalFlush),
+            // Counterfeited two pair
+            ("3h 3d", "2h 4d", "9h 9d Kc Ks Ah", Ordering::Equal, Hand::TwoPair),
+            // The wheel is the lowest straight
+            ("Ah 2d", "6c 7h", "3s 4s 5h Kd Kc", Ordering::Less, Hand::Straight),
+            // Kicker to quads on the board
+            ("Ah 2d", "Kc Qh", "9h 9d 9c 9s 3c", Ordering::Greater, Hand::FourOfAKind),
+        ];
+
+        /// Checks the showdown semantics of an evaluator, from both players points of view
+        pub(crate) fn assert_showdowns(evaluator: fn([Card; 7]) -> HandRank) {
+            for (player, opponent, board, outcome, winning_hand) in SHOWDOWNS {
+                let board: [Card; 5] = cards(board);
+                let evaluate = |hand: [Card; 2]| {
+                    let mut cards = [Card::default(); 7];
+                    cards[..2].copy_from_slice(&hand);
+                    cards[2..].copy_from_slice(&board);
+                    evaluator(cards)
+                };
+                let player = evaluate(cards(player));
+                let opponent = evaluate(cards(opponent));
+
+                let showdown = Showdown::new(player, opponent);
+                let reversed = Showdown::new(opponent, player);
+                match outcome {
+                    Ordering::Greater => {
+                        assert_eq!(showdown, Showdown::Win(player));
+                        assert_eq!(reversed, Showdown::Lose(player));
+                    }
+                    Ordering::Equal => {
+                        assert_eq!(showdown, Showdown::Tie(player));
+                        assert_eq!(reversed, Showdown::Tie(opponent));
+                    }
+                    Ordering::Less => {
+                        assert_eq!(showdown, Showdown::Lose(opponent));
+                        assert_eq!(reversed, Showdown::Win(opponent));
+                    }
+                }
+                assert_eq!(showdown.winning_hand().hand(), winning_hand);
+            }
+        }
+
+        #[test]
+        fn hand_rank_showdowns() {
+            assert_showdowns(hand_rank);
+        }
+
+        #[test]
+        fn hand_order() {
+            assert!(Hand::RoyalFlush > Hand::StraightFlush);
+            assert!(Hand::FullHouse > Hand::Flush);
+            assert!(Hand::Pair > Hand::HighCard);
+            assert!(HandRank::MIN < hand_rank(cards("2h 3d 4c 5s 7h 8d 9c")));
         }
     }
-    results
-}
 
-/// Creates a full poker deck, without the given present cards in it.
-/// Returns None, if there are any duplicates in the present cards
-fn create_deck_without_present_cards<const NUM_CARDS: usize>(
-    present_cards: [Card; NUM_CARDS],
-) -> Option<[Card; FULL_DECK_SIZE - NUM_CARDS]> {
-    let iter = (2..=14)
-        .flat_map(|value| (0..=3).map(move |color| (value, color)))
-        .map(TryInto::try_into)
-        .map(Result::unwrap)
-        .filter(|card| !present_cards.contains(card));
+    mod calculate {
+        use super::{cards, dead_leaving};
+        use crate::{
+            Card, Enumeration, Error, Settings, Showdown, calculate, calculate_with,
+            card_set::CardSet, combinations::Combinations, evaluate,
+        };
+        use std::num::NonZeroUsize;
+
+        #[test]
+        fn board_plays() {
+            let results = calculate(cards("2h 3d"), &cards::<5>("As Ks Qs Js Ts")).unwrap();
+            assert_eq!((results.wins, results.draws, results.losses), (0, 990, 0));
+            assert_eq!(results.equity(), 0.5);
+        }
 
-    array_from_iter_exact(iter)
-}
+        #[test]
+        fn nuts() {
+            let results = calculate(cards("Ah Kh"), &cards::<5>("Qh Jh Th 2c 3d")).unwrap();
+            assert_eq!((results.wins, results.draws, results.losses), (990, 0, 0));
+        }
 
-fn array_from_iter_exact<T, const N: usize>(mut iter: impl Iterator<Item = T>) -> Option<[T; N]> {
-    let array = array::try_from_fn(|_| iter.next());
+        #[test]
+        fn threads_deterministic() {
+            let hand = cards("Qc Qs");
+            let board: [Card; 4] = cards("Ah 9h 5d Qh");
+
+            let settings = |threads, enumeration| Settings {
+                threads: NonZeroUsize::new(threads).unwrap(),
+                enumeration,
+            };
+            let single = calculate_with(hand, &board, &[], settings(1, Enumeration::All)).unwrap();
+            for threads in [2, 3, 7, 64] {
+                assert_eq!(
+                    calculate_with(hand, &board, &[], settings(threads, Enumeration::All)).unwrap(),
+                    single
+                );
+            }
+            assert_eq!(single.total(), 1035 * 44);
+        }
 
-    // If the iterator isnt used up
-    if iter.next().is_some() { None } else { array }
-}
+        #[test]
+        fn suit_isomorphic() {
+            let settings = |enumeration| Settings {
+                threads: NonZeroUsize::new(2).unwrap(),
+                enumeration,
+            };
+            for (hand, board) in [
+                // Only the clubs and spades can be swapped
+                ("Qc Qs", "Ah 9h 5d Qh"),
+                ("7h 7d", "2c 3c 4s 5s"),
+                ("Ah Kh", "2h 9h Kd 4c"),
+                ("2h 3d", "Th Td Tc Ts"),
+            ] {
+                let (hand, board) = (cards(hand), cards::<4>(board));
+                assert_eq!(
+                    calculate_with(hand, &board, &[], settings(Enumeration::SuitIsomorphic))
+                        .unwrap(),
+                    calculate_with(hand, &board, &[], settings(Enumeration::All)).unwrap(),
+                );
+            }
+        }
 
-#[cfg(test)]
-mod tests {
-    use crate::{Card, CardValue, Color, combine_cards_with_indices};
+        #[test]
+        fn dead_cards() {
+            let hand = cards("Ah Kd");
+            let board: [Card; 4] = cards("Kh 7c 7s 2h");
+            let dead: [Card; 2] = cards("Ad 7h");
+
+            let settings = |enumeration| Settings {
+                threads: NonZeroUsize::new(2).unwrap(),
+                enumeration,
+            };
+            let results =
+                calculate_with(hand, &board, &dead, settings(Enumeration::SuitIsomorphic)).unwrap();
+            assert_eq!(
+                results,
+                calculate_with(hand, &board, &dead, settings(Enumeration::All)).unwrap()
+            );
+            // 44 cards are left in the deck
+            assert_eq!(results.total(), 946 * 42);
+            assert_ne!(results, calculate(hand, &board).unwrap());
+
+            for dead in ["Ah", "7c", "5h 5h"] {
+                let dead: Vec<Card> = dead.split(' ').map(|card| card.parse().unwrap()).collect();
+                assert!(matches!(
+                    calculate_with(hand, &board, &dead, Settings::default()),
+                    Err(Error::DuplicateCard(_))
+                ));
+            }
+
+            // Only two cards are left for the river and the opponent hand
+            let dead = dead_leaving(&[hand.as_slice(), &board].concat(), 2);
+            assert_eq!(
+                calculate_with(hand, &board, &dead, Settings::default()),
+                Err(Error::NotEnoughCards { needed: 3, left: 2 })
+            );
+        }
+
+        /// Compares the turn against evaluating every opponent hand and river directly
+        #[test]
+        fn turn() {
+            let hand = cards("Ah Kd");
+            let board: [Card; 4] = cards("Kh 7c 7s 2h");
+            let results = calculate(hand, &board).unwrap();
+
+            let remaining_deck: Vec<Card> =
+                (!CardSet::try_from_cards(hand.into_iter().chain(board)).unwrap())
+                    .iter()
+                    .collect();
+            let (mut wins, mut draws, mut losses) = (0, 0, 0);
+            for opponent in Combinations::new(remaining_deck.len(), 2) {
+                for (river_index, &river) in remaining_deck.iter().enumerate() {
+                    if opponent.contains(&river_index) {
+                        continue;
+                    }
+                    let player_hand = evaluate([
+                        hand[0], hand[1], board[0], board[1], board[2], board[3], river,
+                    ]);
+                    let opponent_hand = evaluate([
+                        remaining_deck[opponent[0]],
+                        remaining_deck[opponent[1]],
+                        board[0],
+                        board[1],
+                        board[2],
+                        board[3],
+                        river,
+                    ]);
+                    match Showdown::new(player_hand, opponent_hand) {
+                        Showdown::Win(_) => wins += 1,
+                        Showdown::Tie(_) => draws += 1,
+                        Showdown::Lose(_) => losses += 1,
+                    }
+                }
+            }
+
+            assert_eq!(
+                (results.wins, results.draws, results.losses),
+                (wins, draws, losses)
+            );
+            assert_eq!(results.total(), 1035 * 44);
+        }
+
+        #[test]
+        fn invalid_cards() {
+            let hand = cards("Ah Kh");
+            let board: [Card; 5] = cards("Qh Jh Th 2c Ah");
+            assert_eq!(
+                calculate(hand, &board).unwrap_err(),
+                Error::DuplicateCard(hand[0])
+            );
+
+            let board: [Card; 6] = cards("Qh Jh Th 2c 3d 4d");
+            assert_eq!(
+                calculate(hand, &board).unwrap_err(),
+                Error::WrongCardCount {
+                    min: 0,
+                    max: 5,
+                    found: 6
+                }
+            );
+        }
+    }
 
     #[test]
-    fn test_combine_cards_with_indices() {
-        let deck = [Card::default()];
-
-        let cards = [Card::new(CardValue::Ace, Color::Clubs); 5];
-        let indices = [0; 2];
-
-        let combined = combine_cards_with_indices(cards, indices, &deck);
-
-        assert_eq!(combined, [
-            Card::new(CardValue::Ace, Color::Clubs),
-            Card::new(CardValue::Ace, Color::Clubs),
-            Card::new(CardValue::Ace, Color::Clubs),
-            Card::new(CardValue::Ace, Color::Clubs),
-            Card::new(CardValue::Ace, Color::Clubs),
-            Card::default(),
-            Card::default(),
-        ]);
+    fn card_index_round_trip() {
+        for index in 0..52 {
+            let card = Card::from_index(index).unwrap();
+            assert_eq!(card.index(), index);
+            assert_eq!(u8::from(card), index);
+            assert_eq!(Card::new(card.value(), card.color()), card);
+            assert_eq!(
+                Card::try_from((card.value().index(), card.color().index())),
+                Ok(card)
+            );
+            assert_eq!(
+                CardValue::from_index(card.value().index()),
+                Some(card.value())
+            );
+            assert_eq!(Color::from_index(card.color().index()), Some(card.color()));
+        }
+
+        assert_eq!(Card::from_index(52), None);
+        assert_eq!(CardValue::from_index(13), None);
+        assert_eq!(Color::from_index(4), None);
+        assert_eq!(Card::new(CardValue::Ace, Color::Spades).index(), 51);
     }
 }
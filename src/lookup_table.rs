@@ -0,0 +1,169 @@
+use crate::{Card, CardValue, Color, HandRank, combinations::num_combinations, highest_hand};
+use std::sync::LazyLock;
+
+/// Number of multisets of 7 values out of 13, which are the 7-combinations of 19 (13 + 7 - 1)
+const NUM_VALUE_MULTISETS: usize = num_combinations(19, 7);
+
+/// Hand ranks of every flush, indexed by the `ValueBitmap` of the flush color
+static FLUSHES: LazyLock<Box<[HandRank]>> = LazyLock::new(|| {
+    (0..1_u16 << 13)
+        .map(|bitmap| {
+            if bitmap.count_ones() < 5 {
+                return HandRank::MIN;
+            }
+
+            let mut cards = [Card::new(CardValue::Two, Color::Diamonds); 7];
+            for (card, value) in cards.iter_mut().zip(values(bitmap)) {
+                *card = Card::new(value, Color::Hearts);
+            }
+            // The other cards cant beat a flush, because there can be at most two of them
+            if bitmap.count_ones() < 7 {
+                cards[6] = Card::new(CardValue::Two, Color::Clubs);
+            }
+
+            highest_hand::hand_rank(cards)
+        })
+        .collect()
+});
+
+/// Hand ranks of every hand without a flush, indexed by `value_multiset_index`
+static VALUE_MULTISETS: LazyLock<Box<[HandRank]>> = LazyLock::new(|| {
+    let mut ranks = vec![HandRank::MIN; NUM_VALUE_MULTISETS].into_boxed_slice();
+
+    let mut counts = [0; 13];
+    fill_value_multisets(&mut counts, 0, 7, &mut ranks);
+
+    ranks
+});
+
+/// Contribution of `count` cards of `value` to the `value_multiset_index`,
+/// if they start at `position` in the sorted values, indexed by `[value][position][count]`
+static CONTRIBUTIONS: [[[u16; 5]; 8]; 13] = {
+    let mut contributions = [[[0; 5]; 8]; 13];
+
+    let mut value = 0;
+    while value < 13 {
+        let mut position = 0;
+        while position < 8 {
+            let mut count = 1;
+            while count < 5 && position + count <= 7 {
+                let last_position = position + count - 1;
+                contributions[value][position][count] = contributions[value][position][count - 1]
+                    + num_combinations(value + last_position, last_position + 1) as u16;
+                count += 1;
+            }
+            position += 1;
+        }
+        value += 1;
+    }
+    contributions
+};
+
+/// Recursively assigns up to `remaining` cards to the values from `value` on,
+/// and evaluates every completed multiset
+fn fill_value_multisets(
+    counts: &mut [u8; 13],
+    value: usize,
+    remaining: u8,
+    ranks: &mut [HandRank],
+) {
+    if remaining == 0 {
+        // Spread the colors, so that there is no flush
+        let mut cards = [Card::default(); 7];
+        let card_values = counts
+            .iter()
+            .enumerate()
+            .flat_map(|(value, &count)| (0..count).map(move |_| CardValue::ALL[value]));
+        for (position, (card, value)) in cards.iter_mut().zip(card_values).enumerate() {
+            *card = Card::new(value, Color::ALL[position % 4]);
+        }
+
+        ranks[value_multiset_index(color_bitmaps(cards))] = highest_hand::hand_rank(cards);
+        return;
+    }
+    if value == 13 {
+        return;
+    }
+
+    for count in 0..=remaining.min(4) {
+        counts[value] = count;
+        fill_value_multisets(counts, value + 1, remaining - count, ranks);
+    }
+    counts[value] = 0;
+}
+
+/// Ranks the sorted values as a combination with repetition (colexicographically),
+/// by mapping the `i`th smallest value `v` to `v + i`
+fn value_multiset_index([a, b, c, d]: [u16; 4]) -> usize {
+    let mut values = a | b | c | d;
+    let mut index = 0;
+    let mut position = 0;
+
+    while values != 0 {
+        let value = values.trailing_zeros();
+        let count = (a >> value & 1) + (b >> value & 1) + (c >> value & 1) + (d >> value & 1);
+
+        index += CONTRIBUTIONS[value as usize][position][count as usize] as usize;
+        position += count as usize;
+
+        // Clear the lowest value
+        values &= values - 1;
+    }
+
+    index
+}
+
+/// One `ValueBitmap` per color
+fn color_bitmaps(cards: [Card; 7]) -> [u16; 4] {
+    let mut color_bitmaps = [0; 4];
+    for card in cards {
+        color_bitmaps[card.color().index() as usize] |= 1 << card.value().index();
+    }
+    color_bitmaps
+}
+
+fn values(bitmap: u16) -> impl Iterator<Item = CardValue> {
+    (0..13)
+        .filter(move |value| bitmap & 1 << value != 0)
+        .map(|value| CardValue::ALL[value])
+}
+
+/// Same as `highest_hand::hand_rank`, but using precomputed tables.
+/// The tables are built on the first call
+pub fn hand_rank(cards: [Card; 7]) -> HandRank {
+    hand_rank_from_bitmaps(color_bitmaps(cards))
+}
+
+/// `hand_rank` of a hand given as one value bitmap per color, which must have exactly seven cards
+pub(crate) fn hand_rank_from_bitmaps(color_bitmaps: [u16; 4]) -> HandRank {
+    // With seven cards, there is at most one flush, and nothing else can beat it
+    if let Some(&flush) = color_bitmaps.iter().find(|bitmap| bitmap.count_ones() >= 5) {
+        return FLUSHES[flush as usize];
+    }
+
+    VALUE_MULTISETS[value_multiset_index(color_bitmaps)]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Card, combinations::Combinations, highest_hand, lookup_table::hand_rank,
+        tests::showdown::assert_showdowns,
+    };
+
+    #[test]
+    fn showdowns() {
+        assert_showdowns(hand_rank);
+    }
+
+    #[test]
+    #[ignore = "enumerates all 133,784,560 hands, run with --release"]
+    fn same_as_bitmaps() {
+        let deck: Vec<Card> = (0..52).filter_map(Card::from_index).collect();
+
+        for indices in Combinations::new(52, 7) {
+            let cards = std::array::from_fn(|position| deck[indices[position]]);
+            assert_eq!(hand_rank(cards), highest_hand::hand_rank(cards));
+        }
+    }
+}
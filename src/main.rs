@@ -1,26 +1,21 @@
-use poker_bot_v2::{calculate, calculate_7, io::get_cards};
+mod batch;
+mod cli;
+mod repl;
 
-/// Runs calculate with the given const lens
-macro_rules! match_len_calculate {
-    ($present_cards:expr, $($num:expr),+) => {
-        match $present_cards.len() {
-            $(
-                $num => {
-                    let present_cards = $present_cards.try_into().unwrap();
-                    calculate::<$num>(present_cards)
-                }
-            ),+
-            _ => unreachable!()
-        }
-    };
-}
-fn main() {
-    let cards = get_cards().unwrap();
+use cli::CliError;
+use std::{env, io, process::ExitCode};
 
-    let results = if cards.len() != 7 {
-        match_len_calculate!(cards, 2, 3, 4, 5, 6)
-    } else {
-        calculate_7(cards.try_into().unwrap())
-    };
-    println!("Results: {results:?}");
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match cli::parse_args(&args).and_then(|parsed| cli::run(&parsed, &mut io::stdout().lock())) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            if let CliError::Usage(_) = error {
+                eprintln!("Run \"poker_bot_v2 help\" for the usage");
+            }
+            ExitCode::from(error.exit_code())
+        }
+    }
 }
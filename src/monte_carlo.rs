@@ -0,0 +1,284 @@
+use crate::{
+    Card, Error, HandRank,
+    card_set::CardSet,
+    create_deck_without_present_cards,
+    multiway::{MultiwayResults, Opponent, Table},
+};
+use std::time::{Duration, Instant};
+
+/// How many samples are drawn between checks of a time budget
+const SAMPLES_PER_CHECK: u64 = 1024;
+/// The fewest samples an estimate can have, since the variance needs at least two
+pub const MIN_SAMPLES: u64 = 2;
+
+/// A small seedable pseudo random number generator (SplitMix64).
+/// The same seed always gives the same numbers, so estimates can be reproduced
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+impl Rng {
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    /// A number in 0..bound, which must not be empty
+    pub fn below(&mut self, bound: usize) -> usize {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+}
+
+/// When to stop drawing samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// A fixed number of samples, at least `MIN_SAMPLES`, which gives the same estimate for the same seed
+    Samples(u64),
+    /// As many samples as fit into the duration, checked every 1024 samples, so at least 1024
+    Time(Duration),
+}
+
+/// Sampled results of every player, with the statistical error of their equities.
+/// Players are indexed like in `MultiwayResults`, with the hero at 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Estimate {
+    results: MultiwayResults,
+}
+impl Estimate {
+    pub fn results(&self) -> &MultiwayResults {
+        &self.results
+    }
+    pub fn samples(&self) -> u64 {
+        self.results.total()
+    }
+    pub fn equity(&self, player: usize) -> f64 {
+        self.results.players()[player].equity()
+    }
+    /// The standard error of the equity of the player
+    pub fn standard_error(&self, player: usize) -> f64 {
+        (self.results.players()[player].variance() / self.samples() as f64).sqrt()
+    }
+    /// The interval of `z` standard errors around the equity, e.g. `z = 1.96` for 95% confidence
+    pub fn confidence_interval(&self, player: usize, z: f64) -> (f64, f64) {
+        let equity = self.equity(player);
+        let margin = z * self.standard_error(player);
+        ((equity - margin).max(0.), (equity + margin).min(1.))
+    }
+}
+
+/// Estimates the results of every player by drawing random remaining boards and random opponent hands,
+/// never drawing the dead cards.
+/// Returns the same errors as `calculate_multiway`, and an error for less than `MIN_SAMPLES` samples
+pub fn estimate(
+    hand: [Card; 2],
+    board: &[Card],
+    dead: &[Card],
+    opponents: &[Opponent],
+    budget: Budget,
+    rng: &mut Rng,
+) -> Result<Estimate, Error> {
+    let table = Table::new(hand, board, dead, opponents)?;
+    if let Budget::Samples(samples) = budget
+        && samples < MIN_SAMPLES
+    {
+        return Err(Error::InvalidSampleCount(samples));
+    }
+    let remaining_pool_size = 5 - board.len();
+    let drawn_cards = remaining_pool_size + 2 * table.random_opponents();
+
+    let mut deck = create_deck_without_present_cards(table.present_cards);
+    let mut results = MultiwayResults::new(table.hands.len());
+    let mut ranks = vec![HandRank::MIN; table.hands.len()];
+
+    let start = Instant::now();
+    let mut samples = 0;
+    loop {
+        match budget {
+            Budget::Samples(max) if samples >= max => break,
+            Budget::Time(max)
+                if samples > 0 && samples % SAMPLES_PER_CHECK == 0 && start.elapsed() >= max =>
+            {
+                break;
+            }
+            _ => {}
+        }
+
+        // Partial Fisher-Yates shuffle, the drawn cards end up at the start of the deck
+        for i in 0..drawn_cards {
+            let j = i + rng.below(deck.len() - i);
+            deck.swap(i, j);
+        }
+        let (pool, mut hole_cards) = deck[..drawn_cards].split_at(remaining_pool_size);
+        let pool = table.present_pool | CardSet::from_iter(pool.iter().copied());
+
+        for (rank, hand) in ranks.iter_mut().zip(&table.hands) {
+            let hand = hand.unwrap_or_else(|| {
+                let (hand, rest) = hole_cards.split_at(2);
+                hole_cards = rest;
+                CardSet::from_iter(hand.iter().copied())
+            });
+            *rank = (pool | hand).hand_rank();
+        }
+        results.record(&ranks);
+        samples += 1;
+    }
+
+    Ok(Estimate { results })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Error, Settings, calculate, calculate_with,
+        io::parse_cards,
+        monte_carlo::{Budget, Rng, estimate},
+        multiway::Opponent,
+        tests::{dead_leaving, hand},
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn reproducible() {
+        let run = |seed| {
+            estimate(
+                hand("AsAd"),
+                &[],
+                &[],
+                &[Opponent::Random, Opponent::Random],
+                Budget::Samples(1000),
+                &mut Rng::new(seed),
+            )
+            .unwrap()
+        };
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1), run(2));
+        assert_eq!(run(1).samples(), 1000);
+    }
+
+    #[test]
+    fn close_to_exact() {
+        let board = parse_cards("Kh7c2d9s").unwrap();
+        let exact = calculate(hand("QsJs"), &board).unwrap().equity();
+
+        let estimate = estimate(
+            hand("QsJs"),
+            &board,
+            &[],
+            &[Opponent::Random],
+            Budget::Samples(100_000),
+            &mut Rng::new(7),
+        )
+        .unwrap();
+
+        let (low, high) = estimate.confidence_interval(0, 3.);
+        assert!(low < exact && exact < high, "{exact} not in {low}..{high}");
+        assert!(estimate.standard_error(0) < 0.002);
+    }
+
+    #[test]
+    fn dead_cards() {
+        // Two random opponents need four cards, but only three are left
+        let river = parse_cards("Kh7c2d9s5h").unwrap();
+        let dead = dead_leaving(&[hand("QsJs").as_slice(), &river].concat(), 3);
+        assert_eq!(
+            estimate(
+                hand("QsJs"),
+                &river,
+                &dead,
+                &[Opponent::Random, Opponent::Random],
+                Budget::Samples(10),
+                &mut Rng::new(0),
+            )
+            .map(|estimate| estimate.samples()),
+            Err(Error::NotEnoughCards { needed: 4, left: 3 })
+        );
+
+        let board = parse_cards("Kh7c2d9s").unwrap();
+        // Three of the queens are gone
+        let dead = parse_cards("QhQdQc").unwrap();
+        let exact = calculate_with(hand("QsJs"), &board, &dead, Settings::default())
+            .unwrap()
+            .equity();
+
+        let estimate = estimate(
+            hand("QsJs"),
+            &board,
+            &dead,
+            &[Opponent::Random],
+            Budget::Samples(100_000),
+            &mut Rng::new(7),
+        )
+        .unwrap();
+        let (low, high) = estimate.confidence_interval(0, 3.);
+        assert!(low < exact && exact < high, "{exact} not in {low}..{high}");
+    }
+
+    #[test]
+    fn multiway_split() {
+        // Everyone plays the board, so every sample is a three way split
+        let board = parse_cards("AhKhQhJhTh").unwrap();
+        let estimate = estimate(
+            hand("2c3c"),
+            &board,
+            &[],
+            &[Opponent::Random, Opponent::Random],
+            Budget::Samples(100),
+            &mut Rng::new(0),
+        )
+        .unwrap();
+
+        for player in 0..3 {
+            assert!((estimate.equity(player) - 1. / 3.).abs() < 1e-12);
+            assert_eq!(estimate.standard_error(player), 0.);
+        }
+    }
+
+    #[test]
+    fn time_budget() {
+        let estimate = estimate(
+            hand("AsAd"),
+            &[],
+            &[],
+            &[Opponent::Random; 8],
+            Budget::Time(Duration::from_millis(20)),
+            &mut Rng::new(0),
+        )
+        .unwrap();
+
+        assert!(estimate.samples() > 0);
+        let equities = estimate.results().equities();
+        assert!((equities.iter().sum::<f64>() - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(
+            estimate(
+                hand("AsAd"),
+                &[],
+                &[],
+                &[Opponent::Hand(hand("AsKs"))],
+                Budget::Samples(1),
+                &mut Rng::new(0),
+            ),
+            Err(Error::DuplicateCard("As".parse().unwrap()))
+        );
+        // A single sample has no variance
+        assert_eq!(
+            estimate(
+                hand("AsAd"),
+                &[],
+                &[],
+                &[Opponent::Random],
+                Budget::Samples(1),
+                &mut Rng::new(0),
+            ),
+            Err(Error::InvalidSampleCount(1))
+        );
+    }
+}
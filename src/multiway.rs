@@ -0,0 +1,361 @@
+use crate::{
+    Card, Error, HandRank, card_set::CardSet, check_board, check_deck_size,
+    create_deck_without_present_cards, incremental::IncrementalCombinations,
+};
+
+/// The most players at a table, including the hero
+pub const MAX_PLAYERS: usize = 10;
+/// The number of shares a pot is split into.
+/// Divisible by every player count up to `MAX_PLAYERS`, so split pots are counted exactly
+const POT_SHARES: u64 = 2520;
+
+/// An opponent of the hero
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opponent {
+    /// Any two cards that are still in the deck
+    Random,
+    /// A known hand
+    Hand([Card; 2]),
+}
+
+/// The results of a single player in a multiway pot
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerResults {
+    wins: u64,
+    ties: u64,
+    losses: u64,
+    /// The pots won in splits, in units of 1/POT_SHARES of a pot
+    tie_shares: u64,
+    /// The sum of the squares of every split pot share, for the variance of sampled results
+    squared_tie_shares: u64,
+}
+impl PlayerResults {
+    /// Showdowns the player won alone
+    pub fn wins(&self) -> u64 {
+        self.wins
+    }
+    /// Showdowns the player split with at least one other player
+    pub fn ties(&self) -> u64 {
+        self.ties
+    }
+    pub fn losses(&self) -> u64 {
+        self.losses
+    }
+    pub fn total(&self) -> u64 {
+        self.wins + self.ties + self.losses
+    }
+    /// The number of pots won in splits, e.g. 1.5 after a three way and a two way split
+    pub fn tie_share(&self) -> f64 {
+        self.tie_shares as f64 / POT_SHARES as f64
+    }
+    /// The share of the pot the player wins on average, with split pots divided among all tied players
+    pub fn equity(&self) -> f64 {
+        (self.wins as f64 + self.tie_share()) / self.total() as f64
+    }
+    /// The sample variance of the share of the pot won in a single showdown
+    pub(crate) fn variance(&self) -> f64 {
+        let total = self.total() as f64;
+        let mean = self.equity();
+        let mean_of_squares = (self.wins as f64
+            + self.squared_tie_shares as f64 / (POT_SHARES * POT_SHARES) as f64)
+            / total;
+        (mean_of_squares - mean * mean).max(0.) * total / (total - 1.)
+    }
+}
+
+/// The results of every player at the table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiwayResults {
+    /// The hero, followed by the opponents in the order they were given
+    players: Vec<PlayerResults>,
+}
+impl MultiwayResults {
+    pub(crate) fn new(players: usize) -> Self {
+        Self {
+            players: vec![PlayerResults::default(); players],
+        }
+    }
+    /// Records a showdown between all players, with their ranks in the same order as the players
+    pub(crate) fn record(&mut self, ranks: &[HandRank]) {
+        let best = ranks.iter().copied().max().unwrap_or(HandRank::MIN);
+        let winners = ranks.iter().filter(|&&rank| rank == best).count() as u64;
+
+        for (player, &rank) in self.players.iter_mut().zip(ranks) {
+            if rank != best {
+                player.losses += 1;
+            } else if winners == 1 {
+                player.wins += 1;
+            } else {
+                player.ties += 1;
+                player.tie_shares += POT_SHARES / winners;
+                player.squared_tie_shares += (POT_SHARES / winners).pow(2);
+            }
+        }
+    }
+    /// The hero, followed by the opponents in the order they were given
+    pub fn players(&self) -> &[PlayerResults] {
+        &self.players
+    }
+    pub fn hero(&self) -> PlayerResults {
+        self.players[0]
+    }
+    /// The number of showdowns, which is the same for every player
+    pub fn total(&self) -> u64 {
+        self.hero().total()
+    }
+    /// The equity of every player, in the same order as `players`. Sums up to 1
+    pub fn equities(&self) -> Vec<f64> {
+        self.players.iter().map(PlayerResults::equity).collect()
+    }
+}
+
+/// The validated cards of a hand between the hero and the opponents
+pub(crate) struct Table {
+    /// The hands of all players, starting with the hero, or None for random opponents
+    pub(crate) hands: Vec<Option<CardSet>>,
+    /// Every card that is known to be out of the deck
+    pub(crate) present_cards: CardSet,
+    /// The cards already on the board
+    pub(crate) present_pool: CardSet,
+}
+impl Table {
+    /// Returns an error on duplicate cards (including the dead ones), if the board has too many cards,
+    /// if there are too few or too many opponents, or if too few cards are left to deal
+    pub(crate) fn new(
+        hand: [Card; 2],
+        board: &[Card],
+        dead: &[Card],
+        opponents: &[Opponent],
+    ) -> Result<Self, Error> {
+        check_board(board)?;
+        if !(1..MAX_PLAYERS).contains(&opponents.len()) {
+            return Err(Error::InvalidOpponentCount {
+                max: MAX_PLAYERS - 1,
+                found: opponents.len(),
+            });
+        }
+
+        let hands = [Some(CardSet::from_iter(hand))]
+            .into_iter()
+            .chain(opponents.iter().map(|opponent| match opponent {
+                Opponent::Random => None,
+                Opponent::Hand(hand) => Some(CardSet::from_iter(*hand)),
+            }))
+            .collect();
+
+        let known_hand_cards = opponents.iter().flat_map(|opponent| match opponent {
+            Opponent::Random => [].iter(),
+            Opponent::Hand(hand) => hand.iter(),
+        });
+        let present_cards = CardSet::try_from_cards(
+            hand.iter()
+                .chain(board)
+                .chain(dead)
+                .chain(known_hand_cards)
+                .copied(),
+        )?;
+
+        let table = Self {
+            hands,
+            present_cards,
+            present_pool: CardSet::from_iter(board.iter().copied()),
+        };
+        check_deck_size(
+            52 - present_cards.len(),
+            5 - board.len() + 2 * table.random_opponents(),
+        )?;
+        Ok(table)
+    }
+    /// The number of random opponents
+    pub(crate) fn random_opponents(&self) -> usize {
+        self.hands.iter().filter(|hand| hand.is_none()).count()
+    }
+}
+
+/// Calculates the results of every player for every remaining board and every hand of the random opponents.
+/// Random opponents are dealt in seat order, so every seat is an own player in the results.
+/// The dead cards are left out of the deck.
+/// Returns an error on duplicate cards (including the dead ones), if the board has too many cards,
+/// if there are too few or too many opponents, or if too few cards are left to deal
+pub fn calculate_multiway(
+    hand: [Card; 2],
+    board: &[Card],
+    dead: &[Card],
+    opponents: &[Opponent],
+) -> Result<MultiwayResults, Error> {
+    let Table {
+        hands,
+        present_cards,
+        present_pool,
+    } = Table::new(hand, board, dead, opponents)?;
+
+    let remaining_deck = create_deck_without_present_cards(present_cards);
+    let remaining_pool_size = 5 - board.len();
+
+    let mut results = MultiwayResults::new(hands.len());
+    let mut ranks = vec![HandRank::MIN; hands.len()];
+
+    for (_, pool) in
+        IncrementalCombinations::new(&remaining_deck, remaining_pool_size, present_pool.into())
+    {
+        let pool = pool.cards();
+        for (rank, hand) in ranks.iter_mut().zip(&hands) {
+            if let Some(hand) = hand {
+                *rank = (pool | *hand).hand_rank();
+            }
+        }
+
+        let available = !(present_cards | pool);
+        deal_random_hands(&mut results, &mut ranks, &hands, 0, pool, available);
+    }
+
+    Ok(results)
+}
+
+/// Deals every possible hand from the available cards to the next random opponent from `seat` on,
+/// and recurses for the following ones. Records the showdown once everyone has a hand
+fn deal_random_hands(
+    results: &mut MultiwayResults,
+    ranks: &mut [HandRank],
+    hands: &[Option<CardSet>],
+    seat: usize,
+    pool: CardSet,
+    available: CardSet,
+) {
+    let Some(player) = (seat..hands.len()).find(|&player| hands[player].is_none()) else {
+        results.record(ranks);
+        return;
+    };
+
+    let mut seconds = available;
+    for first in available {
+        seconds.remove(first);
+        for second in seconds {
+            let hand = CardSet::from_iter([first, second]);
+            ranks[player] = (pool | hand).hand_rank();
+            deal_random_hands(results, ranks, hands, player + 1, pool, available - hand);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Card, Error, Settings, calculate, calculate_with,
+        io::parse_cards,
+        multiway::{MAX_PLAYERS, Opponent, calculate_multiway},
+        tests::{dead_leaving, hand},
+    };
+
+    #[test]
+    fn heads_up_same_as_calculate() {
+        let board = parse_cards("Kh7c2d9s").unwrap();
+        let hero = hand("AsAd");
+
+        let expected = calculate(hero, &board).unwrap();
+        let results = calculate_multiway(hero, &board, &[], &[Opponent::Random]).unwrap();
+        let hero = results.hero();
+
+        assert_eq!(
+            (hero.wins(), hero.ties(), hero.losses()),
+            (expected.wins(), expected.draws(), expected.losses())
+        );
+        assert_eq!(hero.equity(), expected.equity());
+    }
+
+    #[test]
+    fn dead_cards() {
+        let board = parse_cards("Kh7c2d9s").unwrap();
+        let dead = parse_cards("Kd7h").unwrap();
+        let hero = hand("AsAd");
+
+        let expected = calculate_with(hero, &board, &dead, Settings::default()).unwrap();
+        let hero = calculate_multiway(hero, &board, &dead, &[Opponent::Random])
+            .unwrap()
+            .hero();
+        assert_eq!(
+            (hero.wins(), hero.ties(), hero.losses()),
+            (expected.wins(), expected.draws(), expected.losses())
+        );
+
+        assert_eq!(
+            calculate_multiway(hand("AsKs"), &[], &dead, &[Opponent::Hand(hand("Kd2c"))]),
+            Err(Error::DuplicateCard("Kd".parse().unwrap()))
+        );
+
+        // Only two cards are left for the river and the opponent hand
+        let dead = dead_leaving(&[hand("AsAd").as_slice(), &board].concat(), 2);
+        assert_eq!(
+            calculate_multiway(hand("AsAd"), &board, &dead, &[Opponent::Random]),
+            Err(Error::NotEnoughCards { needed: 3, left: 2 })
+        );
+        let opponent = Opponent::Hand(hand("KsKc"));
+        let dead: Vec<Card> = dead
+            .into_iter()
+            .filter(|card| !hand("KsKc").contains(card))
+            .collect();
+        assert!(calculate_multiway(hand("AsAd"), &board, &dead, &[opponent]).is_ok());
+    }
+
+    #[test]
+    fn three_way_split() {
+        // The board plays for everyone
+        let board = parse_cards("AhKhQhJhTh").unwrap();
+        let opponents = [Opponent::Hand(hand("2c3c")), Opponent::Hand(hand("2d3d"))];
+        let results = calculate_multiway(hand("4s5s"), &board, &[], &opponents).unwrap();
+
+        assert_eq!(results.total(), 1);
+        for player in results.players() {
+            assert_eq!((player.wins(), player.ties(), player.losses()), (0, 1, 0));
+            assert!((player.equity() - 1. / 3.).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn random_seats() {
+        let board = parse_cards("Kh7c2d9s5h").unwrap();
+        let results = calculate_multiway(
+            hand("AsAd"),
+            &board,
+            &[],
+            &[
+                Opponent::Random,
+                Opponent::Hand(hand("KsKd")),
+                Opponent::Random,
+            ],
+        )
+        .unwrap();
+
+        // C(43, 2) hands for the first random seat, C(41, 2) for the second
+        assert_eq!(results.total(), 903 * 820);
+        // Both random seats are dealt from the same cards
+        assert_eq!(results.players()[1], results.players()[3]);
+        // The kings have a set
+        let equities = results.equities();
+        assert!((equities.iter().sum::<f64>() - 1.).abs() < 1e-9);
+        assert!(equities[2] > equities[0]);
+    }
+
+    #[test]
+    fn invalid_input() {
+        let hero = hand("AsAd");
+        assert_eq!(
+            calculate_multiway(hero, &[], &[], &[]),
+            Err(Error::InvalidOpponentCount {
+                max: MAX_PLAYERS - 1,
+                found: 0
+            })
+        );
+        assert_eq!(
+            calculate_multiway(hero, &[], &[], &[Opponent::Random; MAX_PLAYERS]),
+            Err(Error::InvalidOpponentCount {
+                max: MAX_PLAYERS - 1,
+                found: MAX_PLAYERS
+            })
+        );
+        assert_eq!(
+            calculate_multiway(hero, &[], &[], &[Opponent::Hand(hand("KsAd"))]),
+            Err(Error::DuplicateCard("Ad".parse().unwrap()))
+        );
+    }
+}
@@ -0,0 +1,399 @@
+use crate::{
+    Card, CardValue, Color, Error,
+    card_set::CardSet,
+    combinations::{Combinations, num_combinations, rank},
+    io::parse_cards,
+};
+use std::{fmt, str::FromStr};
+
+/// The number of different two card hands
+pub const NUM_COMBOS: usize = num_combinations(52, 2);
+
+/// A weighted set of two card hands, like "QQ+, AKs, A5s-A2s, 76s, AKo:0.5".
+/// Every combo has a weight between 0 (not in the range) and 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    /// Indexed by the rank of the card indices of the combo
+    weights: Vec<f64>,
+}
+impl Range {
+    /// An empty range
+    pub fn new() -> Self {
+        Self {
+            weights: vec![0.; NUM_COMBOS],
+        }
+    }
+    /// Every combo with a weight of 1
+    pub fn full() -> Self {
+        Self {
+            weights: vec![1.; NUM_COMBOS],
+        }
+    }
+    fn index(hand: [Card; 2]) -> usize {
+        let (first, second) = (hand[0].index(), hand[1].index());
+        rank(
+            52,
+            &[first.min(second) as usize, first.max(second) as usize],
+        )
+    }
+    pub fn weight(&self, hand: [Card; 2]) -> f64 {
+        self.weights[Self::index(hand)]
+    }
+    /// Sets the weight of the combo, which is clamped to 0..=1
+    pub fn set_weight(&mut self, hand: [Card; 2], weight: f64) {
+        debug_assert_ne!(hand[0], hand[1]);
+        self.weights[Self::index(hand)] = weight.clamp(0., 1.);
+    }
+    pub fn is_empty(&self) -> bool {
+        self.weights.iter().all(|&weight| weight == 0.)
+    }
+    /// All combos with a weight above 0, together with their weight
+    pub fn combos(&self) -> impl Iterator<Item = ([Card; 2], f64)> + '_ {
+        Combinations::new(52, 2)
+            .zip(&self.weights)
+            .filter(|&(_, &weight)| weight > 0.)
+            .map(|(indices, &weight)| {
+                let card = |index: usize| Card::from_index(index as u8).expect("Below 52");
+                ([card(indices[0]), card(indices[1])], weight)
+            })
+    }
+    /// The sum of the weights of all combos that dont contain any of the dead cards
+    pub fn num_combos(&self, dead: CardSet) -> f64 {
+        self.combos()
+            .filter(|(hand, _)| !hand.iter().any(|&card| dead.contains(card)))
+            .map(|(_, weight)| weight)
+            .sum()
+    }
+}
+impl Default for Range {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a class of starting hands (like "AK") is suited, offsuit or both
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Suitedness {
+    Suited,
+    Offsuit,
+    Any,
+}
+
+/// All combos of the starting hand class, e.g. the 6 pocket pairs or the 4 suited combos
+pub(crate) fn class_combos(
+    high: CardValue,
+    low: CardValue,
+    suitedness: Suitedness,
+) -> Vec<[Card; 2]> {
+    let mut combos = Vec::new();
+    for high_color in Color::ALL {
+        for low_color in Color::ALL {
+            let suited = high_color == low_color;
+            let included = if high == low {
+                // Every pair of different colors once
+                high_color.index() < low_color.index()
+            } else {
+                match suitedness {
+                    Suitedness::Suited => suited,
+                    Suitedness::Offsuit => !suited,
+                    Suitedness::Any => true,
+                }
+            };
+            if included {
+                combos.push([Card::new(high, high_color), Card::new(low, low_color)]);
+            }
+        }
+    }
+    combos
+}
+
+/// Parses a single starting hand class like "AKs", "T9", or "QQ" into (high, low, suitedness)
+pub(crate) fn parse_class(class: &str) -> Option<(CardValue, CardValue, Suitedness)> {
+    let mut chars = class.chars();
+    let first = CardValue::from_char(chars.next()?)?;
+    let second = CardValue::from_char(chars.next()?)?;
+    let suitedness = match chars.next() {
+        None => Suitedness::Any,
+        Some('s' | 'S') if first != second => Suitedness::Suited,
+        Some('o' | 'O') if first != second => Suitedness::Offsuit,
+        Some(_) => return None,
+    };
+    if chars.next().is_some() {
+        return None;
+    }
+    Some((first.max(second), first.min(second), suitedness))
+}
+
+/// Expands one token of range notation (without the weight) into its combos
+fn parse_token(token: &str) -> Option<Vec<[Card; 2]>> {
+    // Explicit combos
+    if let Ok(cards) = parse_cards(token) {
+        return match cards[..] {
+            [first, second] if first != second => Some(vec![[first, second]]),
+            _ => None,
+        };
+    }
+
+    // The (high, low) value indices of all classes in the token
+    let (classes, suitedness): (Vec<(u8, u8)>, _) =
+        if let Some((top, bottom)) = token.split_once('-') {
+            let (top_high, top_low, suitedness) = parse_class(top)?;
+            let (bottom_high, bottom_low, bottom_suitedness) = parse_class(bottom)?;
+            if suitedness != bottom_suitedness {
+                return None;
+            }
+
+            let (top_high, bottom_high) = (top_high.index(), bottom_high.index());
+            let (top_low, bottom_low) = (top_low.index(), bottom_low.index());
+            if top_high == top_low && bottom_high == bottom_low {
+                // Pairs, e.g. "QQ-99"
+                let values = top_high.min(bottom_high)..=top_high.max(bottom_high);
+                (values.map(|value| (value, value)).collect(), suitedness)
+            } else if top_high == bottom_high && top_high != top_low && bottom_high != bottom_low {
+                // Same high card, e.g. "A5s-A2s"
+                let lows = top_low.min(bottom_low)..=top_low.max(bottom_low);
+                (lows.map(|low| (top_high, low)).collect(), suitedness)
+            } else {
+                return None;
+            }
+        } else if let Some(class) = token.strip_suffix('+') {
+            let (high, low, suitedness) = parse_class(class)?;
+            let (high, low) = (high.index(), low.index());
+            if high == low {
+                // Every pair from this one up, e.g. "QQ+"
+                ((high..13).map(|value| (value, value)).collect(), suitedness)
+            } else {
+                // Every kicker up to one below the high card, e.g. "A9s+"
+                ((low..high).map(|low| (high, low)).collect(), suitedness)
+            }
+        } else {
+            let (high, low, suitedness) = parse_class(token)?;
+            (vec![(high.index(), low.index())], suitedness)
+        };
+
+    let value = |index| CardValue::from_index(index).expect("Below 13");
+    Some(
+        classes
+            .into_iter()
+            .flat_map(|(high, low)| class_combos(value(high), value(low), suitedness))
+            .collect(),
+    )
+}
+
+impl FromStr for Range {
+    type Err = Error;
+    /// Comma separated standard range notation. Every token can have a weight like ":0.5",
+    /// later tokens overwrite the weights of earlier ones
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut range = Self::new();
+
+        for token in s
+            .split(',')
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+        {
+            let invalid = || Error::InvalidRange(token.to_string());
+
+            let (hands, weight) = match token.split_once(':') {
+                Some((hands, weight)) => {
+                    let weight: f64 = weight.trim().parse().map_err(|_| invalid())?;
+                    if !(0. ..=1.).contains(&weight) {
+                        return Err(invalid());
+                    }
+                    (hands.trim(), weight)
+                }
+                None => (token, 1.),
+            };
+
+            for combo in parse_token(hands).ok_or_else(invalid)? {
+                range.set_weight(combo, weight);
+            }
+        }
+
+        Ok(range)
+    }
+}
+
+impl fmt::Display for Range {
+    /// The most compact notation, e.g. "QQ+, A5s-A2s, AKo:0.5, AsKs".
+    /// Classes that are only partially in the range, or with different weights, are written as explicit combos
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tokens = Vec::new();
+        let mut explicit = Vec::new();
+
+        // The weight of every combo of the class, if they are all the same
+        let mut class_weight = |high, low, suitedness| {
+            let combos = class_combos(high, low, suitedness);
+            let weight = self.weight(combos[0]);
+            if combos.iter().all(|&combo| self.weight(combo) == weight) {
+                Some(weight).filter(|&weight| weight > 0.)
+            } else {
+                explicit.extend(
+                    combos
+                        .into_iter()
+                        .filter(|&combo| self.weight(combo) > 0.)
+                        .map(|combo| (combo, self.weight(combo))),
+                );
+                None
+            }
+        };
+
+        let value = |index| CardValue::from_index(index).expect("Below 13");
+
+        // Pairs, runs are written as "QQ+" or "QQ-99"
+        let pairs: Vec<_> = (0..13)
+            .rev()
+            .map(|index| class_weight(value(index), value(index), Suitedness::Any))
+            .collect();
+        for (top, bottom, weight) in runs(&pairs) {
+            let (top, bottom) = (value(12 - top as u8), value(12 - bottom as u8));
+            tokens.push(match (top, bottom) {
+                _ if top == bottom => (format!("{top}{top}"), weight),
+                (CardValue::Ace, _) => (format!("{bottom}{bottom}+"), weight),
+                _ => (format!("{top}{top}-{bottom}{bottom}"), weight),
+            });
+        }
+
+        // Suited and offsuit hands, runs with the same high card are written as "A9s+" or "A5s-A2s"
+        for (suitedness, suffix) in [(Suitedness::Suited, 's'), (Suitedness::Offsuit, 'o')] {
+            for high in (1..13).rev() {
+                let kickers: Vec<_> = (0..high)
+                    .rev()
+                    .map(|low| class_weight(value(high), value(low), suitedness))
+                    .collect();
+                let high = value(high);
+                for (top, bottom, weight) in runs(&kickers) {
+                    let top_index = high.index() - 1 - top as u8;
+                    let (top, bottom) = (value(top_index), value(high.index() - 1 - bottom as u8));
+                    tokens.push(match top {
+                        _ if top == bottom => (format!("{high}{top}{suffix}"), weight),
+                        _ if top_index == high.index() - 1 => {
+                            (format!("{high}{bottom}{suffix}+"), weight)
+                        }
+                        _ => (
+                            format!("{high}{top}{suffix}-{high}{bottom}{suffix}"),
+                            weight,
+                        ),
+                    });
+                }
+            }
+        }
+
+        for ([first, second], weight) in explicit {
+            // Higher card first, like in the classes
+            let (first, second) = if first.value() >= second.value() {
+                (first, second)
+            } else {
+                (second, first)
+            };
+            tokens.push((format!("{first}{second}"), weight));
+        }
+
+        for (index, (token, weight)) in tokens.into_iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{token}")?;
+            if weight != 1. {
+                write!(f, ":{weight}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Maximal runs of neighbouring entries with the same weight, as (first position, last position, weight)
+fn runs(weights: &[Option<f64>]) -> Vec<(usize, usize, f64)> {
+    let mut runs: Vec<(usize, usize, f64)> = Vec::new();
+    for (position, weight) in weights.iter().enumerate() {
+        let Some(weight) = *weight else {
+            continue;
+        };
+        match runs.last_mut() {
+            Some((_, last, run_weight)) if *last + 1 == position && *run_weight == weight => {
+                *last = position;
+            }
+            _ => runs.push((position, position, weight)),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, card_set::CardSet, io::parse_cards, range::Range};
+
+    fn range(range: &str) -> Range {
+        range.parse().unwrap()
+    }
+
+    #[test]
+    fn num_combos() {
+        for (notation, combos) in [
+            ("AKs", 4.),
+            ("AKo", 12.),
+            ("AK", 16.),
+            ("QQ", 6.),
+            ("QQ+", 18.),
+            ("22+", 78.),
+            ("QQ-99", 24.),
+            ("A5s-A2s", 16.),
+            ("KTs+", 12.),
+            ("AsKs", 1.),
+            ("AKs:0.5", 2.),
+            ("QQ+, AKs, A5s-A2s, 76s", 42.),
+            ("AK, AKo:0", 4.),
+            ("", 0.),
+        ] {
+            assert_eq!(
+                range(notation).num_combos(CardSet::EMPTY),
+                combos,
+                "{notation}"
+            );
+        }
+        assert_eq!(Range::full().num_combos(CardSet::EMPTY), 1326.);
+    }
+
+    #[test]
+    fn dead_cards() {
+        let dead: CardSet = parse_cards("AsKd").unwrap().into_iter().collect();
+        assert_eq!(range("AA").num_combos(dead), 3.);
+        assert_eq!(range("AKs").num_combos(dead), 2.);
+        assert_eq!(range("AKo").num_combos(dead), 7.);
+        assert_eq!(Range::full().num_combos(dead), 1225.);
+    }
+
+    #[test]
+    fn display() {
+        for notation in [
+            "QQ+, AKs, A5s-A2s, 76s",
+            "AA",
+            "JJ-99:0.5, 22",
+            "KTs+, AKo:0.25",
+            "AsKs, 7h2d",
+            "",
+        ] {
+            assert_eq!(range(notation).to_string(), notation);
+        }
+        assert_eq!(range("AK").to_string(), "AKs, AKo");
+        assert_eq!(range("A2s+, 99-TT").to_string(), "TT-99, A2s+");
+        assert_eq!(
+            range("AKs, AsKs:0.5").to_string(),
+            "AhKh, AdKd, AcKc, AsKs:0.5"
+        );
+        assert_eq!(range(&Range::full().to_string()), Range::full());
+    }
+
+    #[test]
+    fn errors() {
+        for notation in [
+            "AAs", "AK+s", "QQ-AKs", "A5s-K2s", "AKs:2", "AKs:x", "As", "AsAs", "1K",
+        ] {
+            assert_eq!(
+                notation.parse::<Range>(),
+                Err(Error::InvalidRange(notation.to_string())),
+                "{notation}"
+            );
+        }
+    }
+}
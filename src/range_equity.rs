@@ -0,0 +1,278 @@
+use crate::{
+    Card, Error, HandRank, card_set::CardSet, check_board, check_deck_size,
+    create_deck_without_present_cards, incremental::IncrementalCombinations, range::Range,
+};
+use std::cmp::Ordering;
+
+/// The results of a single combo against the opponent range,
+/// with every showdown weighted by the weight of the opponent combo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComboResults {
+    pub combo: [Card; 2],
+    /// The weight of the combo in its own range
+    pub weight: f64,
+    wins: f64,
+    draws: f64,
+    losses: f64,
+}
+impl ComboResults {
+    fn new(combo: [Card; 2], weight: f64) -> Self {
+        Self {
+            combo,
+            weight,
+            wins: 0.,
+            draws: 0.,
+            losses: 0.,
+        }
+    }
+    fn record(&mut self, ordering: Ordering, weight: f64) {
+        match ordering {
+            Ordering::Greater => self.wins += weight,
+            Ordering::Equal => self.draws += weight,
+            Ordering::Less => self.losses += weight,
+        }
+    }
+    pub fn wins(&self) -> f64 {
+        self.wins
+    }
+    pub fn draws(&self) -> f64 {
+        self.draws
+    }
+    pub fn losses(&self) -> f64 {
+        self.losses
+    }
+    pub fn total(&self) -> f64 {
+        self.wins + self.draws + self.losses
+    }
+    /// The share of the pot the combo wins on average against the opponent range
+    pub fn equity(&self) -> f64 {
+        (self.wins + self.draws / 2.) / self.total()
+    }
+}
+
+/// The results of both ranges, broken down into their combos
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeResults {
+    /// Every hero combo that doesnt conflict with the board, the dead cards or the whole villain range
+    pub hero: Vec<ComboResults>,
+    /// Every villain combo that doesnt conflict with the board, the dead cards or the whole hero range
+    pub villain: Vec<ComboResults>,
+}
+impl RangeResults {
+    /// The equity of the hero range, with every combo weighted by its own weight
+    pub fn equity(&self) -> f64 {
+        let (won, total) = self.hero.iter().fold((0., 0.), |(won, total), combo| {
+            (
+                won + combo.weight * (combo.wins + combo.draws / 2.),
+                total + combo.weight * combo.total(),
+            )
+        });
+        won / total
+    }
+}
+
+/// Calculates the equity of the hand against every combo of the villain range, over every remaining board.
+/// Villain combos that conflict with the hand, board or dead cards are left out
+pub fn hand_vs_range(
+    hand: [Card; 2],
+    villain: &Range,
+    board: &[Card],
+    dead: &[Card],
+) -> Result<RangeResults, Error> {
+    CardSet::try_from_cards(
+        hand.into_iter()
+            .chain(board.iter().copied())
+            .chain(dead.iter().copied()),
+    )?;
+
+    let mut hero = Range::new();
+    hero.set_weight(hand, 1.);
+    range_vs_range(&hero, villain, board, dead)
+}
+
+/// Calculates the equity of every combo of both ranges against the other range, over every remaining board.
+/// Pairs of combos that share a card are left out, as well as combos that conflict with the board,
+/// the dead cards or every opponent combo.
+/// This enumerates every pair of combos on every board, so it is meant for the flop and later streets, or small ranges.
+/// Returns an error on duplicate board or dead cards, if the board has too many cards,
+/// if too few cards are left to deal, or if a range has no combos left
+pub fn range_vs_range(
+    hero: &Range,
+    villain: &Range,
+    board: &[Card],
+    dead: &[Card],
+) -> Result<RangeResults, Error> {
+    check_board(board)?;
+    let present_cards = CardSet::try_from_cards(board.iter().chain(dead).copied())?;
+    let present_pool = CardSet::from_iter(board.iter().copied());
+
+    let combos = |range: &Range| -> Vec<(ComboResults, CardSet)> {
+        range
+            .combos()
+            .map(|(combo, weight)| (ComboResults::new(combo, weight), CardSet::from_iter(combo)))
+            .filter(|(_, cards)| cards.is_disjoint(present_cards))
+            .collect()
+    };
+    let (mut hero, mut villain) = (combos(hero), combos(villain));
+
+    let remaining_deck = create_deck_without_present_cards(present_cards);
+    // Both combos are dealt from the deck as well
+    check_deck_size(remaining_deck.len(), 5 - board.len() + 4)?;
+    let (mut hero_ranks, mut villain_ranks) = (vec![None; hero.len()], vec![None; villain.len()]);
+
+    for (_, pool) in
+        IncrementalCombinations::new(&remaining_deck, 5 - board.len(), present_pool.into())
+    {
+        let pool = pool.cards();
+        // The rank of every combo that doesnt conflict with this board
+        let rank = |(_, cards): &(ComboResults, CardSet)| -> Option<HandRank> {
+            cards.is_disjoint(pool).then(|| (pool | *cards).hand_rank())
+        };
+        for (rank_slot, combo) in hero_ranks.iter_mut().zip(&hero) {
+            *rank_slot = rank(combo);
+        }
+        for (rank_slot, combo) in villain_ranks.iter_mut().zip(&villain) {
+            *rank_slot = rank(combo);
+        }
+
+        for ((hero_combo, hero_cards), hero_rank) in hero.iter_mut().zip(&hero_ranks) {
+            let Some(hero_rank) = hero_rank else {
+                continue;
+            };
+            for ((villain_combo, villain_cards), villain_rank) in
+                villain.iter_mut().zip(&villain_ranks)
+            {
+                let Some(villain_rank) = villain_rank else {
+                    continue;
+                };
+                if !hero_cards.is_disjoint(*villain_cards) {
+                    continue;
+                }
+
+                let ordering = hero_rank.cmp(villain_rank);
+                hero_combo.record(ordering, villain_combo.weight);
+                villain_combo.record(ordering.reverse(), hero_combo.weight);
+            }
+        }
+    }
+
+    // Leave out the combos that conflict with every opponent combo
+    let results = |combos: Vec<(ComboResults, CardSet)>| -> Result<Vec<ComboResults>, Error> {
+        let results: Vec<_> = combos
+            .into_iter()
+            .map(|(results, _)| results)
+            .filter(|results| results.total() > 0.)
+            .collect();
+        if results.is_empty() {
+            return Err(Error::EmptyRange);
+        }
+        Ok(results)
+    };
+    Ok(RangeResults {
+        hero: results(hero)?,
+        villain: results(villain)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Error, calculate,
+        io::parse_cards,
+        range::Range,
+        range_equity::{hand_vs_range, range_vs_range},
+        tests::{dead_leaving, hand},
+    };
+
+    #[test]
+    fn same_as_calculate() {
+        let board = parse_cards("Kh7c2d").unwrap();
+        let results = hand_vs_range(hand("AsAd"), &Range::full(), &board, &[]).unwrap();
+
+        // Every opponent combo has the same number of runouts, so the weighted equity is the same
+        let expected = calculate(hand("AsAd"), &board).unwrap();
+        assert!((results.equity() - expected.equity()).abs() < 1e-12);
+        assert_eq!(results.villain.len(), 1081);
+        assert_eq!(results.hero[0].total(), expected.total() as f64);
+    }
+
+    #[test]
+    fn breakdown() {
+        let board = parse_cards("Kh7c2d").unwrap();
+        let villain: Range = "KK, 77, AK:0.5, QJs".parse().unwrap();
+        let results = hand_vs_range(hand("AsAd"), &villain, &board, &[]).unwrap();
+
+        // The hero and board block all but 3 KK, 3 77, 1 AKs and 5 AKo combos
+        assert_eq!(results.villain.len(), 3 + 3 + 1 + 5 + 4);
+        for combo in &results.villain {
+            // Only the sets are ahead
+            let [first, second] = combo.combo;
+            assert_eq!(first.value() == second.value(), combo.equity() > 0.5);
+        }
+        assert_eq!(
+            results
+                .villain
+                .iter()
+                .filter(|combo| combo.weight == 0.5)
+                .count(),
+            6
+        );
+
+        // The dead cards leave 1 KK, 1 77, 4 AKo and the 3 QJs without the queen of hearts
+        let dead = parse_cards("Kc7dQh").unwrap();
+        let results = hand_vs_range(hand("AsAd"), &villain, &board, &dead).unwrap();
+        assert_eq!(results.villain.len(), 1 + 1 + 4 + 3);
+    }
+
+    #[test]
+    fn symmetric() {
+        let board = parse_cards("Th9h2c4d").unwrap();
+        let (hero, villain): (Range, Range) =
+            ("JJ+, AQs".parse().unwrap(), "88-66, KQs".parse().unwrap());
+        let results = range_vs_range(&hero, &villain, &board, &[]).unwrap();
+        let reversed = range_vs_range(&villain, &hero, &board, &[]).unwrap();
+
+        assert!((results.equity() + reversed.equity() - 1.).abs() < 1e-12);
+        assert_eq!(results.hero, reversed.villain);
+    }
+
+    #[test]
+    fn invalid() {
+        let board = parse_cards("AsAdAh").unwrap();
+        assert_eq!(
+            hand_vs_range(hand("AcKd"), &"AA".parse().unwrap(), &board, &[]),
+            Err(Error::EmptyRange)
+        );
+        assert_eq!(
+            hand_vs_range(hand("AsKd"), &Range::full(), &board, &[]),
+            Err(Error::DuplicateCard("As".parse().unwrap()))
+        );
+        // The last ace is dead
+        assert_eq!(
+            hand_vs_range(
+                hand("KcKd"),
+                &"AK".parse().unwrap(),
+                &board,
+                &parse_cards("Ac").unwrap()
+            ),
+            Err(Error::EmptyRange)
+        );
+        assert_eq!(
+            range_vs_range(&Range::full(), &Range::full(), &board, &board[..1]),
+            Err(Error::DuplicateCard("As".parse().unwrap()))
+        );
+
+        // Both combos and the turn and river need six cards, but only five are left
+        let known = [board.as_slice(), &parse_cards("KsKdQsQd2c").unwrap()].concat();
+        let dead = dead_leaving(&known, 0);
+        assert_eq!(
+            range_vs_range(
+                &"KK".parse().unwrap(),
+                &"QQ".parse().unwrap(),
+                &board,
+                &dead
+            ),
+            Err(Error::NotEnoughCards { needed: 6, left: 5 })
+        );
+    }
+}
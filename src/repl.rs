@@ -0,0 +1,375 @@
+//! An interactive session for a hand in progress
+
+use crate::cli::{CliError, cards, draws, list, percent};
+use poker_bot_v2::{
+    Card, Error, Settings,
+    analysis::analyze,
+    calculate_with,
+    card_set::CardSet,
+    check_board,
+    io::parse_cards,
+    monte_carlo::{Budget, Rng, estimate},
+    multiway::{MAX_PLAYERS, Opponent},
+    range::Range,
+    range_equity::hand_vs_range,
+};
+use std::{
+    io::{BufRead, Write},
+    mem,
+};
+
+/// The samples of the equity estimates, where an exact enumeration would take too long
+const SAMPLES: u64 = 200_000;
+
+const HELP: &str = "\
+Enter cards to set the hand (the first two cards) and then to add them to the board, like \"AsKd Qh7c2d\".
+Commands:
+  hand <cards>      Replaces the hand
+  flop <cards>      Adds the flop to the empty board, \"turn <card>\" and \"river <card>\" work the same
+  board <cards>     Adds any cards to the board
+  dead <cards>      Marks cards as dead, so that they cant come anymore
+  opponents <n>     Plays against n random hands
+  range <range>     Plays against a single opponent with the range, like \"QQ+, AKs\"
+  undo              Reverts the last change
+  reset             Starts a new hand
+  history           Lists the changes since the start
+  help              Prints this message
+  quit              Ends the session
+";
+
+/// Who the hero plays against
+#[derive(Debug, Clone, PartialEq)]
+enum Villain {
+    Random(usize),
+    Range(Range),
+}
+
+/// The known cards and opponents of the hand in progress
+#[derive(Debug, Clone, PartialEq)]
+struct Spot {
+    hand: Option<[Card; 2]>,
+    board: Vec<Card>,
+    dead: Vec<Card>,
+    villain: Villain,
+}
+impl Default for Spot {
+    fn default() -> Self {
+        Self {
+            hand: None,
+            board: Vec::new(),
+            dead: Vec::new(),
+            villain: Villain::Random(1),
+        }
+    }
+}
+impl Spot {
+    /// Returns an error on duplicate cards, too many board cards or an invalid number of opponents
+    fn validate(&self) -> Result<(), Error> {
+        check_board(&self.board)?;
+        if let Villain::Random(opponents) = self.villain
+            && !(1..MAX_PLAYERS).contains(&opponents)
+        {
+            return Err(Error::InvalidOpponentCount {
+                max: MAX_PLAYERS - 1,
+                found: opponents,
+            });
+        }
+        CardSet::try_from_cards(
+            self.hand
+                .into_iter()
+                .flatten()
+                .chain(self.board.iter().copied())
+                .chain(self.dead.iter().copied()),
+        )?;
+        Ok(())
+    }
+}
+
+/// What a line of input does
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    /// Replaces the spot, which still has to be validated
+    Change(Spot),
+    Undo,
+    History,
+    Help,
+    Quit,
+}
+
+fn parse_line(spot: &Spot, line: &str) -> Result<Action, CliError> {
+    let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let mut spot = spot.clone();
+
+    match command.to_lowercase().as_str() {
+        "hand" => {
+            let hand = parse_cards(rest)?;
+            let found = hand.len();
+            spot.hand = Some(hand.try_into().map_err(|_| Error::WrongCardCount {
+                min: 2,
+                max: 2,
+                found,
+            })?);
+        }
+        street @ ("flop" | "turn" | "river") => {
+            let (board_len, count) = match street {
+                "flop" => (0, 3),
+                "turn" => (3, 1),
+                _ => (4, 1),
+            };
+            if spot.board.len() != board_len {
+                return Err(CliError::Usage(format!(
+                    "The {street} needs a board with {board_len} cards"
+                )));
+            }
+            let cards = parse_cards(rest)?;
+            if cards.len() != count {
+                return Err(Error::WrongCardCount {
+                    min: count,
+                    max: count,
+                    found: cards.len(),
+                }
+                .into());
+            }
+            spot.board.extend(cards);
+        }
+        "board" => spot.board.extend(parse_cards(rest)?),
+        "dead" => spot.dead.extend(parse_cards(rest)?),
+        "opponents" => {
+            let opponents = rest
+                .trim()
+                .parse()
+                .map_err(|_| CliError::Usage(format!("Invalid number of opponents \"{rest}\"")))?;
+            spot.villain = Villain::Random(opponents);
+        }
+        "range" => spot.villain = Villain::Range(rest.parse()?),
+        "reset" => spot = Spot::default(),
+        "undo" => return Ok(Action::Undo),
+        "history" => return Ok(Action::History),
+        "help" => return Ok(Action::Help),
+        "quit" | "exit" => return Ok(Action::Quit),
+        _ => {
+            let cards = parse_cards(line)
+                .map_err(|_| CliError::Usage(format!("Unknown command \"{command}\"")))?;
+            match spot.hand {
+                Some(_) => spot.board.extend(cards),
+                None if cards.len() >= 2 => {
+                    spot.hand = Some([cards[0], cards[1]]);
+                    spot.board.extend(&cards[2..]);
+                }
+                None => {
+                    return Err(CliError::Usage("Enter both hand cards first".to_string()));
+                }
+            }
+        }
+    }
+    Ok(Action::Change(spot))
+}
+
+/// Writes the cards, the made hand, draws, outs and equity of the spot
+fn show(spot: &Spot, out: &mut impl Write) -> Result<(), CliError> {
+    let Some(hand) = spot.hand else {
+        writeln!(out, "Enter your hand, like \"AsKd\"")?;
+        return Ok(());
+    };
+
+    let villain = match &spot.villain {
+        Villain::Random(1) => "1 random hand".to_string(),
+        Villain::Random(opponents) => format!("{opponents} random hands"),
+        Villain::Range(range) => range.to_string(),
+    };
+    write!(
+        out,
+        "Hand: {} | Board: {}",
+        cards(&hand),
+        cards(&spot.board)
+    )?;
+    if !spot.dead.is_empty() {
+        write!(out, " | Dead: {}", cards(&spot.dead))?;
+    }
+    writeln!(out, " | Against: {villain}")?;
+
+    let analysis = analyze(hand, &spot.board)?;
+    writeln!(
+        out,
+        "Made hand: {} | Draws: {}",
+        analysis.hand,
+        list(&draws(&analysis))
+    )?;
+    if matches!(spot.board.len(), 3 | 4) {
+        let outs: Vec<Card> = analysis
+            .outs
+            .into_iter()
+            .filter(|out| !spot.dead.contains(out))
+            .collect();
+        writeln!(out, "Outs ({}): {}", outs.len(), cards(&outs))?;
+    }
+
+    // A failed calculation still leaves a valid spot, like a range that conflicts with the board
+    match equity(hand, spot) {
+        Ok(equity) => writeln!(out, "Equity: {equity}")?,
+        Err(error) => writeln!(out, "Equity: {error}")?,
+    }
+    Ok(())
+}
+
+/// Exact from the flop on against a single random hand or a range, estimated otherwise
+fn equity(hand: [Card; 2], spot: &Spot) -> Result<String, Error> {
+    let (board, dead) = (&spot.board, &spot.dead);
+    match &spot.villain {
+        Villain::Random(1) if board.len() >= 3 => Ok(percent(
+            calculate_with(hand, board, dead, Settings::default())?.equity(),
+        )),
+        Villain::Random(opponents) => {
+            let opponents = vec![Opponent::Random; *opponents];
+            let estimate = estimate(
+                hand,
+                board,
+                dead,
+                &opponents,
+                Budget::Samples(SAMPLES),
+                &mut Rng::new(0),
+            )?;
+            Ok(format!(
+                "{} ± {}",
+                percent(estimate.equity(0)),
+                percent(1.96 * estimate.standard_error(0))
+            ))
+        }
+        Villain::Range(range) if board.len() >= 3 => {
+            Ok(percent(hand_vs_range(hand, range, board, dead)?.equity()))
+        }
+        Villain::Range(_) => Ok("against a range from the flop on".to_string()),
+    }
+}
+
+/// Reads commands from the input until it ends or the user quits, and writes the updated spot after every change
+pub fn run(mut input: impl BufRead, out: &mut impl Write) -> Result<(), CliError> {
+    writeln!(
+        out,
+        "Enter your hand and the board, like \"AsKd Qh7c2d\", or \"help\" for all commands"
+    )?;
+
+    let mut spot = Spot::default();
+    // The spots before every change, and the lines that made them
+    let mut undo: Vec<Spot> = Vec::new();
+    let mut history: Vec<String> = Vec::new();
+
+    let mut line = String::new();
+    loop {
+        write!(out, "> ")?;
+        out.flush()?;
+        line.clear();
+        if input.read_line(&mut line).map_err(Error::from)? == 0 {
+            return Ok(());
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let action = match parse_line(&spot, line) {
+            Ok(action) => action,
+            Err(error) => {
+                writeln!(out, "Error: {error}")?;
+                continue;
+            }
+        };
+        match action {
+            Action::Change(changed) => {
+                if let Err(error) = changed.validate() {
+                    writeln!(out, "Error: {error}")?;
+                    continue;
+                }
+                undo.push(mem::replace(&mut spot, changed));
+                history.push(line.to_string());
+                show(&spot, out)?;
+            }
+            Action::Undo => match undo.pop() {
+                Some(previous) => {
+                    spot = previous;
+                    history.pop();
+                    show(&spot, out)?;
+                }
+                None => writeln!(out, "Nothing to undo")?,
+            },
+            Action::History if history.is_empty() => writeln!(out, "No changes yet")?,
+            Action::History => {
+                for (i, line) in history.iter().enumerate() {
+                    writeln!(out, "{}: {line}", i + 1)?;
+                }
+            }
+            Action::Help => write!(out, "{HELP}")?,
+            Action::Quit => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repl::{Action, Spot, Villain, parse_line, run};
+    use poker_bot_v2::io::parse_cards;
+
+    fn session(input: &str) -> String {
+        let mut out = Vec::new();
+        run(input.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let Ok(Action::Change(spot)) = parse_line(&Spot::default(), "AsKd Qh7c2d") else {
+            panic!()
+        };
+        assert_eq!(spot.hand.unwrap().to_vec(), parse_cards("AsKd").unwrap());
+        assert_eq!(spot.board, parse_cards("Qh7c2d").unwrap());
+
+        let Ok(Action::Change(turn)) = parse_line(&spot, "turn 5s") else {
+            panic!()
+        };
+        assert_eq!(turn.board.len(), 4);
+        assert!(parse_line(&spot, "river 5s").is_err());
+        assert!(parse_line(&spot, "flop 5s4s3s").is_err());
+
+        let Ok(Action::Change(range)) = parse_line(&spot, "range QQ+, AKs") else {
+            panic!()
+        };
+        assert!(matches!(range.villain, Villain::Range(_)));
+        assert_eq!(
+            parse_line(&spot, "reset"),
+            Ok(Action::Change(Spot::default()))
+        );
+        assert!(parse_line(&spot, "fold").is_err());
+    }
+
+    #[test]
+    fn session_steps() {
+        let out = session(
+            "AhKh Qh7h2c5d\nriver 9s\nundo\ndead 3h\nopponents 0\nAh\nhistory\nundo\nundo\nundo\nquit\nhelp\n",
+        );
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert!(
+            lines[1].starts_with("> Hand: Ah Kh | Board: Qh 7h 2c 5d | Against: 1 random hand")
+        );
+        assert!(lines[2].starts_with("Made hand: High card | Draws: flush draw"));
+        assert!(lines[3].starts_with("Outs (15): "));
+        assert!(lines[4].starts_with("Equity: "));
+
+        // The river and its undo
+        assert!(lines[6].starts_with("Made hand: High card | Draws: none"));
+        assert!(!lines[7].starts_with("Outs"));
+        assert!(lines[8].contains("Board: Qh 7h 2c 5d |"));
+
+        // The dead three of hearts is no out anymore
+        assert!(lines[12].contains("Dead: 3h"));
+        assert!(lines[14].starts_with("Outs (14): "));
+        assert!(lines[15].starts_with("Equity: "));
+
+        assert!(out.contains("Error: Expected between 1 and 9 opponents, found 0"));
+        assert!(out.contains("Error: Ah was given more than once"));
+        assert!(out.contains("1: AhKh Qh7h2c5d\n2: dead 3h\n"));
+        assert!(out.contains("Nothing to undo"));
+        // The session ended before the help
+        assert!(!out.contains("Commands:"));
+    }
+}
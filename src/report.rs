@@ -0,0 +1,364 @@
+use crate::{
+    Card, Hand, Results,
+    analysis::{Analysis, StraightDraw},
+    distribution::{Distribution, EquityHistogram},
+    monte_carlo::Estimate,
+    multiway::{MultiwayResults, PlayerResults},
+    range_equity::RangeResults,
+};
+use std::fmt::Write;
+
+/// A single field of a `Report`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Integer(u64),
+    /// Non-finite floats are written as null in JSON and left empty in CSV
+    Float(f64),
+    Text(String),
+    /// A JSON array, separated by spaces in CSV
+    List(Vec<Value>),
+}
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Self::Integer(value)
+    }
+}
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Self::Integer(value as u64)
+    }
+}
+impl From<u8> for Value {
+    fn from(value: u8) -> Self {
+        Self::Integer(value.into())
+    }
+}
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Self::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+/// A flat list of named values, which can be written as a JSON object or a CSV row.
+/// The fields keep the order they were added in
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Report {
+    fields: Vec<(String, Value)>,
+}
+impl Report {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds a field, replacing the value if the name already exists
+    pub fn push(&mut self, name: impl Into<String>, value: impl Into<Value>) {
+        let (name, value) = (name.into(), value.into());
+        match self.fields.iter_mut().find(|(field, _)| *field == name) {
+            Some((_, old)) => *old = value,
+            None => self.fields.push((name, value)),
+        }
+    }
+    /// Like `push`, for chaining
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.push(name, value);
+        self
+    }
+    /// Adds every field of the other report, with the prefix before their names
+    pub fn extend(&mut self, prefix: &str, other: Report) {
+        for (name, value) in other.fields {
+            self.push(format!("{prefix}{name}"), value);
+        }
+    }
+    pub fn fields(&self) -> &[(String, Value)] {
+        &self.fields
+    }
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+
+    /// A single line JSON object
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{");
+        for (i, (name, value)) in self.fields.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write_json_string(&mut json, name);
+            json.push(':');
+            write_json_value(&mut json, value);
+        }
+        json.push('}');
+        json
+    }
+    /// The names of the fields, separated by commas
+    pub fn csv_header(&self) -> String {
+        let names: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, _)| csv_field(name))
+            .collect();
+        names.join(",")
+    }
+    /// The values of the fields, in the same order as `csv_header`
+    pub fn csv_row(&self) -> String {
+        let values: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(_, value)| csv_field(&csv_value(value)))
+            .collect();
+        values.join(",")
+    }
+}
+
+fn write_json_string(json: &mut String, string: &str) {
+    json.push('"');
+    for char in string.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if char.is_control() => {
+                // Writing into a string cant fail
+                let _ = write!(json, "\\u{:04x}", char as u32);
+            }
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+}
+
+fn write_json_value(json: &mut String, value: &Value) {
+    match value {
+        Value::Bool(bool) => json.push_str(if *bool { "true" } else { "false" }),
+        Value::Integer(integer) => json.push_str(&integer.to_string()),
+        Value::Float(float) if float.is_finite() => json.push_str(&float.to_string()),
+        Value::Float(_) => json.push_str("null"),
+        Value::Text(text) => write_json_string(json, text),
+        Value::List(values) => {
+            json.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                write_json_value(json, value);
+            }
+            json.push(']');
+        }
+    }
+}
+
+fn csv_value(value: &Value) -> String {
+    match value {
+        Value::Bool(bool) => bool.to_string(),
+        Value::Integer(integer) => integer.to_string(),
+        Value::Float(float) if float.is_finite() => float.to_string(),
+        Value::Float(_) => String::new(),
+        Value::Text(text) => text.clone(),
+        Value::List(values) => {
+            let values: Vec<String> = values.iter().map(csv_value).collect();
+            values.join(" ")
+        }
+    }
+}
+
+/// Quotes the field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Types that can be written as a `Report`
+pub trait ToReport {
+    fn to_report(&self) -> Report;
+}
+
+/// The counts, their share in percent, and the equity
+fn showdown_report(wins: u64, ties: u64, losses: u64, equity: f64) -> Report {
+    let total = wins + ties + losses;
+    let percent = |count: u64| count as f64 / total as f64 * 100.;
+    Report::new()
+        .with("wins", wins)
+        .with("ties", ties)
+        .with("losses", losses)
+        .with("total", total)
+        .with("win_percent", percent(wins))
+        .with("tie_percent", percent(ties))
+        .with("loss_percent", percent(losses))
+        .with("equity", equity)
+}
+
+impl ToReport for Results {
+    fn to_report(&self) -> Report {
+        showdown_report(self.wins, self.draws, self.losses, self.equity())
+    }
+}
+impl ToReport for PlayerResults {
+    fn to_report(&self) -> Report {
+        showdown_report(self.wins(), self.ties(), self.losses(), self.equity())
+    }
+}
+/// The results of the hero, and the equities of every player
+impl ToReport for MultiwayResults {
+    fn to_report(&self) -> Report {
+        let mut report = Report::new().with("players", self.players().len());
+        report.extend("", self.hero().to_report());
+        report.with("equities", self.equities())
+    }
+}
+/// The results of the hero, with the 95% confidence interval of the equity
+impl ToReport for Estimate {
+    fn to_report(&self) -> Report {
+        let (low, high) = self.confidence_interval(0, 1.96);
+        let mut report = self.results().to_report();
+        report.push("samples", self.samples());
+        report.push("standard_error", self.standard_error(0));
+        report.push("confidence_low", low);
+        report.push("confidence_high", high);
+        report
+    }
+}
+impl ToReport for RangeResults {
+    fn to_report(&self) -> Report {
+        let equity = self.equity();
+        Report::new()
+            .with("hero_equity", equity)
+            .with("villain_equity", 1. - equity)
+            .with("hero_combos", self.hero.len())
+            .with("villain_combos", self.villain.len())
+    }
+}
+/// The results, and the frequency of every hand category of both players, like `hero_two_pair`
+impl ToReport for Distribution {
+    fn to_report(&self) -> Report {
+        let mut report = self.results().to_report();
+        let name = |hand: Hand| hand.to_string().to_lowercase().replace(' ', "_");
+        for hand in Hand::ALL {
+            report.push(format!("hero_{}", name(hand)), self.hero_frequency(hand));
+        }
+        for hand in Hand::ALL {
+            report.push(
+                format!("opponent_{}", name(hand)),
+                self.opponent_frequency(hand),
+            );
+        }
+        report
+    }
+}
+impl ToReport for EquityHistogram {
+    fn to_report(&self) -> Report {
+        Report::new()
+            .with("runouts", self.runouts())
+            .with("expected_hand_strength", self.expected_hand_strength())
+            .with(
+                "expected_squared_hand_strength",
+                self.expected_squared_hand_strength(),
+            )
+            .with("bins", self.bins().to_vec())
+    }
+}
+impl ToReport for Analysis {
+    fn to_report(&self) -> Report {
+        let straight_draw = match self.straight_draw {
+            StraightDraw::None => "none",
+            StraightDraw::Gutshot => "gutshot",
+            StraightDraw::OpenEnded => "open_ended",
+        };
+        let texture = self.texture;
+        Report::new()
+            .with("made_hand", self.hand.to_string())
+            .with("board_hand", self.board_hand.to_string())
+            .with("flush_draw", self.flush_draw)
+            .with("backdoor_flush_draw", self.backdoor_flush_draw)
+            .with("straight_draw", straight_draw)
+            .with("backdoor_straight_draw", self.backdoor_straight_draw)
+            .with("overcards", self.overcards)
+            .with(
+                "outs",
+                self.outs.iter().map(Card::to_string).collect::<Vec<_>>(),
+            )
+            .with("paired", texture.paired)
+            .with("monotone", texture.monotone)
+            .with("two_tone", texture.two_tone)
+            .with("rainbow", texture.rainbow)
+            .with("connected", texture.connected)
+            .with("straight_possible", texture.straight_possible)
+            .with("flush_possible", texture.flush_possible)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        HandRank, Results, Showdown,
+        report::{Report, ToReport, Value},
+    };
+
+    #[test]
+    fn json() {
+        let report = Report::new()
+            .with("name", "say \"hi\"\n")
+            .with("count", 3_u64)
+            .with("share", 0.25)
+            .with("nan", f64::NAN)
+            .with("flags", vec![true, false]);
+        assert_eq!(
+            report.to_json(),
+            r#"{"name":"say \"hi\"\n","count":3,"share":0.25,"nan":null,"flags":[true,false]}"#
+        );
+        assert_eq!(Report::new().to_json(), "{}");
+    }
+
+    #[test]
+    fn csv() {
+        let report = Report::new()
+            .with("cards", vec!["As", "Kd"])
+            .with("note", "a, \"b\"")
+            .with("nan", f64::NAN);
+        assert_eq!(report.csv_header(), "cards,note,nan");
+        assert_eq!(report.csv_row(), r#"As Kd,"a, ""b""","#);
+    }
+
+    #[test]
+    fn results() {
+        let mut results = Results::default();
+        results.record(Showdown::Win(HandRank::MIN), 3);
+        results.record(Showdown::Tie(HandRank::MIN), 1);
+
+        let report = results.to_report();
+        assert_eq!(report.get("total"), Some(&Value::Integer(4)));
+        assert_eq!(report.get("win_percent"), Some(&Value::Float(75.)));
+        assert_eq!(report.get("equity"), Some(&Value::Float(0.875)));
+        assert_eq!(
+            report.csv_header(),
+            "wins,ties,losses,total,win_percent,tie_percent,loss_percent,equity"
+        );
+    }
+}